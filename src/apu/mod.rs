@@ -0,0 +1 @@
+pub(crate) mod sound;
\ No newline at end of file
@@ -0,0 +1,660 @@
+//https://gbdev.io/pandocs/Audio.html  &&   https://gbdev.io/pandocs/Audio_Registers.html
+
+use crate::state::{StateError, StateReader, StateWriter};
+
+pub const SAMPLE_RATE: u32 = 44_100;
+const CPU_FREQ: u32 = 4_194_304;
+// Frame sequencer is clocked at 512 Hz
+const FRAME_SEQUENCER_PERIOD: u32 = 8192;
+
+const DUTY_TABLE: [u8; 4] = [0b0000_0001, 0b1000_0001, 0b1000_0111, 0b0111_1110];
+const NOISE_DIVISORS: [u32; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+
+// Bits that always read back as 1 for FF10..FF26
+const READ_MASKS: [u8; 0x17] = [
+    0x80, 0x3F, 0x00, 0xFF, 0xBF,   //NR10-NR14
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF,   //unused FF15, NR21-NR24
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF,   //NR30-NR34
+    0xFF, 0xFF, 0x00, 0x00, 0xBF,   //unused FF1F, NR41-NR44
+    0x00, 0x00, 0x70,               //NR50-NR52
+];
+
+#[derive(Default)]
+struct Envelope {
+    initial_volume: u8,
+    increase: bool,
+    period: u8,
+    volume: u8,
+    timer: u8,
+}
+
+impl Envelope {
+    fn write(&mut self, value: u8) {
+        self.initial_volume = value >> 4;
+        self.increase = value & 0x08 != 0;
+        self.period = value & 0x07;
+    }
+
+    fn trigger(&mut self) {
+        self.volume = self.initial_volume;
+        self.timer = self.period;
+    }
+
+    fn step(&mut self) {
+        if self.period == 0 {
+            return;
+        }
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer == 0 {
+            self.timer = self.period;
+            if self.increase && self.volume < 15 {
+                self.volume += 1;
+            } else if !self.increase && self.volume > 0 {
+                self.volume -= 1;
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Length {
+    counter: u16,
+    enabled: bool,
+}
+
+impl Length {
+    //Return true when the channel has to be turned off
+    fn step(&mut self) -> bool {
+        if self.enabled && self.counter > 0 {
+            self.counter -= 1;
+            return self.counter == 0;
+        }
+        false
+    }
+}
+
+#[derive(Default)]
+struct Square {
+    enabled: bool,
+    dac_enabled: bool,
+    duty: u8,
+    duty_position: u8,
+    frequency: u16,
+    timer: u32,
+    length: Length,
+    envelope: Envelope,
+    //Sweep is only used by the channel 1
+    sweep_period: u8,
+    sweep_negate: bool,
+    sweep_shift: u8,
+    sweep_timer: u8,
+    sweep_enabled: bool,
+    shadow_frequency: u16,
+}
+
+impl Square {
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        if self.length.counter == 0 {
+            self.length.counter = 64;
+        }
+        self.timer = (2048 - u32::from(self.frequency)) * 4;
+        self.envelope.trigger();
+
+        self.shadow_frequency = self.frequency;
+        self.sweep_timer = if self.sweep_period == 0 { 8 } else { self.sweep_period };
+        self.sweep_enabled = self.sweep_period != 0 || self.sweep_shift != 0;
+        if self.sweep_shift != 0 && self.sweep_frequency() > 2047 {
+            self.enabled = false;
+        }
+    }
+
+    fn run(&mut self, ticks: u32) {
+        let mut ticks = ticks;
+        while ticks >= self.timer {
+            ticks -= self.timer;
+            self.timer = (2048 - u32::from(self.frequency)) * 4;
+            self.duty_position = (self.duty_position + 1) & 0x07;
+        }
+        self.timer -= ticks;
+    }
+
+    fn sweep_frequency(&self) -> u16 {
+        let delta = self.shadow_frequency >> self.sweep_shift;
+        if self.sweep_negate {
+            self.shadow_frequency.wrapping_sub(delta)
+        } else {
+            self.shadow_frequency + delta
+        }
+    }
+
+    fn step_sweep(&mut self) {
+        if self.sweep_timer > 0 {
+            self.sweep_timer -= 1;
+        }
+        if self.sweep_timer != 0 {
+            return;
+        }
+        self.sweep_timer = if self.sweep_period == 0 { 8 } else { self.sweep_period };
+        if !self.sweep_enabled || self.sweep_period == 0 {
+            return;
+        }
+
+        let new_frequency = self.sweep_frequency();
+        if new_frequency > 2047 {
+            self.enabled = false;
+        } else if self.sweep_shift != 0 {
+            self.shadow_frequency = new_frequency;
+            self.frequency = new_frequency;
+            //Overflow check is made a second time with the new frequency
+            if self.sweep_frequency() > 2047 {
+                self.enabled = false;
+            }
+        }
+    }
+
+    fn output(&self) -> u8 {
+        if !self.enabled {
+            return 0;
+        }
+        let high = DUTY_TABLE[self.duty as usize] & (1 << self.duty_position) != 0;
+        if high { self.envelope.volume } else { 0 }
+    }
+}
+
+#[derive(Default)]
+struct Wave {
+    enabled: bool,
+    dac_enabled: bool,
+    volume_code: u8,
+    frequency: u16,
+    timer: u32,
+    position: u8,
+    length: Length,
+    ram: [u8; 0x10],
+}
+
+impl Wave {
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        if self.length.counter == 0 {
+            self.length.counter = 256;
+        }
+        self.timer = (2048 - u32::from(self.frequency)) * 2;
+        self.position = 0;
+    }
+
+    fn run(&mut self, ticks: u32) {
+        let mut ticks = ticks;
+        while ticks >= self.timer {
+            ticks -= self.timer;
+            self.timer = (2048 - u32::from(self.frequency)) * 2;
+            self.position = (self.position + 1) & 0x1F;
+        }
+        self.timer -= ticks;
+    }
+
+    fn output(&self) -> u8 {
+        if !self.enabled {
+            return 0;
+        }
+        //2 samples per byte, upper nibble first
+        let byte = self.ram[(self.position >> 1) as usize];
+        let sample = if self.position & 1 == 0 { byte >> 4 } else { byte & 0x0F };
+        match self.volume_code {
+            1 => sample,
+            2 => sample >> 1,
+            3 => sample >> 2,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Noise {
+    enabled: bool,
+    dac_enabled: bool,
+    clock_shift: u8,
+    width_mode: bool,
+    divisor_code: u8,
+    timer: u32,
+    lfsr: u16,
+    length: Length,
+    envelope: Envelope,
+}
+
+impl Noise {
+    fn period(&self) -> u32 {
+        NOISE_DIVISORS[self.divisor_code as usize] << self.clock_shift
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        if self.length.counter == 0 {
+            self.length.counter = 64;
+        }
+        self.timer = self.period();
+        self.lfsr = 0x7FFF;
+        self.envelope.trigger();
+    }
+
+    fn run(&mut self, ticks: u32) {
+        let mut ticks = ticks;
+        while ticks >= self.timer {
+            ticks -= self.timer;
+            self.timer = self.period();
+            //xor of the 2 low bits goes in bit 14 (and bit 6 in 7 bits mode)
+            let bit = (self.lfsr & 0x01) ^ ((self.lfsr >> 1) & 0x01);
+            self.lfsr = (self.lfsr >> 1) | (bit << 14);
+            if self.width_mode {
+                self.lfsr = (self.lfsr & !0x40) | (bit << 6);
+            }
+        }
+        self.timer -= ticks;
+    }
+
+    fn output(&self) -> u8 {
+        if !self.enabled || self.lfsr & 0x01 != 0 {
+            return 0;
+        }
+        self.envelope.volume
+    }
+}
+
+pub struct Apu {
+    //Raw value of FF10..FF26, used to read back the registers
+    registers: [u8; 0x17],
+    enabled: bool,
+    channel1: Square,
+    channel2: Square,
+    channel3: Wave,
+    channel4: Noise,
+    frame_sequencer: u8,
+    frame_counter: u32,
+    sample_counter: u32,
+    //Interleaved stereo samples (left, right) at SAMPLE_RATE
+    pub(crate) samples: Vec<f32>,
+}
+
+impl Apu {
+    pub fn new() -> Self {
+        let mut apu = Self {
+            registers: [0; 0x17],
+            enabled: false,
+            channel1: Square::default(),
+            channel2: Square::default(),
+            channel3: Wave::default(),
+            channel4: Noise::default(),
+            frame_sequencer: 0,
+            frame_counter: 0,
+            sample_counter: 0,
+            samples: Vec::new(),
+        };
+        //Values left by the boot rom
+        apu.write(0xFF26, 0x80);
+        apu.write(0xFF24, 0x77);
+        apu.write(0xFF25, 0xF3);
+        apu
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            0xFF26 => {
+                (if self.enabled { 0x80 } else { 0 })
+                    | READ_MASKS[0x16]
+                    | (if self.channel1.enabled { 0x01 } else { 0 })
+                    | (if self.channel2.enabled { 0x02 } else { 0 })
+                    | (if self.channel3.enabled { 0x04 } else { 0 })
+                    | (if self.channel4.enabled { 0x08 } else { 0 })
+            }
+            0xFF10..=0xFF25 => {
+                let index = (address - 0xFF10) as usize;
+                self.registers[index] | READ_MASKS[index]
+            }
+            0xFF30..=0xFF3F => self.channel3.ram[(address & 0x0F) as usize],
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        if address == 0xFF26 {
+            self.write_power(value & 0x80 != 0);
+            return;
+        }
+        if (0xFF30..=0xFF3F).contains(&address) {
+            self.channel3.ram[(address & 0x0F) as usize] = value;
+            return;
+        }
+        //Registers are read only while the APU is off
+        if !self.enabled || !(0xFF10..=0xFF25).contains(&address) {
+            return;
+        }
+        self.registers[(address - 0xFF10) as usize] = value;
+
+        match address {
+            0xFF10 => {
+                self.channel1.sweep_period = (value >> 4) & 0x07;
+                self.channel1.sweep_negate = value & 0x08 != 0;
+                self.channel1.sweep_shift = value & 0x07;
+            }
+            0xFF11 => {
+                self.channel1.duty = value >> 6;
+                self.channel1.length.counter = 64 - u16::from(value & 0x3F);
+            }
+            0xFF12 => {
+                self.channel1.envelope.write(value);
+                self.channel1.dac_enabled = value & 0xF8 != 0;
+                self.channel1.enabled &= self.channel1.dac_enabled;
+            }
+            0xFF13 => self.channel1.frequency = (self.channel1.frequency & 0x700) | u16::from(value),
+            0xFF14 => {
+                self.channel1.frequency = (self.channel1.frequency & 0xFF) | (u16::from(value & 0x07) << 8);
+                self.channel1.length.enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    self.channel1.trigger();
+                }
+            }
+            0xFF16 => {
+                self.channel2.duty = value >> 6;
+                self.channel2.length.counter = 64 - u16::from(value & 0x3F);
+            }
+            0xFF17 => {
+                self.channel2.envelope.write(value);
+                self.channel2.dac_enabled = value & 0xF8 != 0;
+                self.channel2.enabled &= self.channel2.dac_enabled;
+            }
+            0xFF18 => self.channel2.frequency = (self.channel2.frequency & 0x700) | u16::from(value),
+            0xFF19 => {
+                self.channel2.frequency = (self.channel2.frequency & 0xFF) | (u16::from(value & 0x07) << 8);
+                self.channel2.length.enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    self.channel2.trigger();
+                }
+            }
+            0xFF1A => {
+                self.channel3.dac_enabled = value & 0x80 != 0;
+                self.channel3.enabled &= self.channel3.dac_enabled;
+            }
+            0xFF1B => self.channel3.length.counter = 256 - u16::from(value),
+            0xFF1C => self.channel3.volume_code = (value >> 5) & 0x03,
+            0xFF1D => self.channel3.frequency = (self.channel3.frequency & 0x700) | u16::from(value),
+            0xFF1E => {
+                self.channel3.frequency = (self.channel3.frequency & 0xFF) | (u16::from(value & 0x07) << 8);
+                self.channel3.length.enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    self.channel3.trigger();
+                }
+            }
+            0xFF20 => self.channel4.length.counter = 64 - u16::from(value & 0x3F),
+            0xFF21 => {
+                self.channel4.envelope.write(value);
+                self.channel4.dac_enabled = value & 0xF8 != 0;
+                self.channel4.enabled &= self.channel4.dac_enabled;
+            }
+            0xFF22 => {
+                self.channel4.clock_shift = value >> 4;
+                self.channel4.width_mode = value & 0x08 != 0;
+                self.channel4.divisor_code = value & 0x07;
+            }
+            0xFF23 => {
+                self.channel4.length.enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    self.channel4.trigger();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn write_power(&mut self, on: bool) {
+        if self.enabled && !on {
+            //Turning the APU off clears every register but the wave RAM
+            let wave_ram = self.channel3.ram;
+            self.registers = [0; 0x17];
+            self.channel1 = Square::default();
+            self.channel2 = Square::default();
+            self.channel3 = Wave { ram: wave_ram, ..Wave::default() };
+            self.channel4 = Noise::default();
+        } else if !self.enabled && on {
+            self.frame_sequencer = 0;
+            self.frame_counter = 0;
+        }
+        self.enabled = on;
+    }
+
+    pub fn run(&mut self, ticks: u32) {
+        if self.enabled {
+            self.channel1.run(ticks);
+            self.channel2.run(ticks);
+            self.channel3.run(ticks);
+            self.channel4.run(ticks);
+
+            self.frame_counter += ticks;
+            while self.frame_counter >= FRAME_SEQUENCER_PERIOD {
+                self.frame_counter -= FRAME_SEQUENCER_PERIOD;
+                self.step_frame_sequencer();
+            }
+        }
+
+        self.sample_counter += ticks * SAMPLE_RATE;
+        while self.sample_counter >= CPU_FREQ {
+            self.sample_counter -= CPU_FREQ;
+            let (left, right) = self.mix();
+            self.samples.push(left);
+            self.samples.push(right);
+        }
+    }
+
+    //Step  Length Ctr  Vol Env     Sweep
+    //0     Clock       -           -
+    //2     Clock       -           Clock
+    //4     Clock       -           -
+    //6     Clock       -           Clock
+    //7     -           Clock       -
+    fn step_frame_sequencer(&mut self) {
+        if self.frame_sequencer & 1 == 0 {
+            if self.channel1.length.step() {
+                self.channel1.enabled = false;
+            }
+            if self.channel2.length.step() {
+                self.channel2.enabled = false;
+            }
+            if self.channel3.length.step() {
+                self.channel3.enabled = false;
+            }
+            if self.channel4.length.step() {
+                self.channel4.enabled = false;
+            }
+        }
+        if self.frame_sequencer == 2 || self.frame_sequencer == 6 {
+            self.channel1.step_sweep();
+        }
+        if self.frame_sequencer == 7 {
+            self.channel1.envelope.step();
+            self.channel2.envelope.step();
+            self.channel4.envelope.step();
+        }
+        self.frame_sequencer = (self.frame_sequencer + 1) % 8;
+    }
+
+    fn mix(&self) -> (f32, f32) {
+        if !self.enabled {
+            return (0.0, 0.0);
+        }
+        //DAC turn the digital value 0..15 into -1.0..1.0
+        let dac = |enabled: bool, value: u8| if enabled { f32::from(value) / 7.5 - 1.0 } else { 0.0 };
+        let outputs = [
+            dac(self.channel1.dac_enabled, self.channel1.output()),
+            dac(self.channel2.dac_enabled, self.channel2.output()),
+            dac(self.channel3.dac_enabled, self.channel3.output()),
+            dac(self.channel4.dac_enabled, self.channel4.output()),
+        ];
+
+        let nr50 = self.registers[0x14];
+        let nr51 = self.registers[0x15];
+        let mut left = 0.0;
+        let mut right = 0.0;
+        for (i, output) in outputs.iter().enumerate() {
+            if nr51 & (0x10 << i) != 0 {
+                left += output;
+            }
+            if nr51 & (0x01 << i) != 0 {
+                right += output;
+            }
+        }
+        let left_volume = f32::from((nr50 >> 4) & 0x07) + 1.0;
+        let right_volume = f32::from(nr50 & 0x07) + 1.0;
+
+        (left / 4.0 * left_volume / 8.0, right / 4.0 * right_volume / 8.0)
+    }
+}
+
+//Save states, the fields in declaration order
+
+impl Envelope {
+    fn save_state(&self, state: &mut StateWriter) {
+        state.u8(self.initial_volume);
+        state.bool(self.increase);
+        state.u8(self.period);
+        state.u8(self.volume);
+        state.u8(self.timer);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.initial_volume = state.u8()?;
+        self.increase = state.bool()?;
+        self.period = state.u8()?;
+        self.volume = state.u8()?;
+        self.timer = state.u8()?;
+        Ok(())
+    }
+}
+
+impl Length {
+    fn save_state(&self, state: &mut StateWriter) {
+        state.u16(self.counter);
+        state.bool(self.enabled);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.counter = state.u16()?;
+        self.enabled = state.bool()?;
+        Ok(())
+    }
+}
+
+impl Square {
+    fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.enabled);
+        state.bool(self.dac_enabled);
+        state.u8(self.duty);
+        state.u8(self.duty_position);
+        state.u16(self.frequency);
+        state.u32(self.timer);
+        self.length.save_state(state);
+        self.envelope.save_state(state);
+        state.u8(self.sweep_period);
+        state.bool(self.sweep_negate);
+        state.u8(self.sweep_shift);
+        state.u8(self.sweep_timer);
+        state.bool(self.sweep_enabled);
+        state.u16(self.shadow_frequency);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.enabled = state.bool()?;
+        self.dac_enabled = state.bool()?;
+        self.duty = state.u8()? & 0x03;
+        self.duty_position = state.u8()? & 0x07;
+        self.frequency = state.u16()? & 0x7FF;
+        self.timer = state.u32()?;
+        self.length.load_state(state)?;
+        self.envelope.load_state(state)?;
+        self.sweep_period = state.u8()?;
+        self.sweep_negate = state.bool()?;
+        self.sweep_shift = state.u8()?;
+        self.sweep_timer = state.u8()?;
+        self.sweep_enabled = state.bool()?;
+        self.shadow_frequency = state.u16()?;
+        Ok(())
+    }
+}
+
+impl Wave {
+    fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.enabled);
+        state.bool(self.dac_enabled);
+        state.u8(self.volume_code);
+        state.u16(self.frequency);
+        state.u32(self.timer);
+        state.u8(self.position);
+        self.length.save_state(state);
+        state.bytes(&self.ram);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.enabled = state.bool()?;
+        self.dac_enabled = state.bool()?;
+        self.volume_code = state.u8()? & 0x03;
+        self.frequency = state.u16()? & 0x7FF;
+        self.timer = state.u32()?;
+        self.position = state.u8()? & 0x1F;
+        self.length.load_state(state)?;
+        state.bytes(&mut self.ram)
+    }
+}
+
+impl Noise {
+    fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.enabled);
+        state.bool(self.dac_enabled);
+        state.u8(self.clock_shift);
+        state.bool(self.width_mode);
+        state.u8(self.divisor_code);
+        state.u32(self.timer);
+        state.u16(self.lfsr);
+        self.length.save_state(state);
+        self.envelope.save_state(state);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.enabled = state.bool()?;
+        self.dac_enabled = state.bool()?;
+        self.clock_shift = state.u8()? & 0x0F;
+        self.width_mode = state.bool()?;
+        self.divisor_code = state.u8()? & 0x07;
+        self.timer = state.u32()?;
+        self.lfsr = state.u16()?;
+        self.length.load_state(state)?;
+        self.envelope.load_state(state)
+    }
+}
+
+impl Apu {
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.bytes(&self.registers);
+        state.bool(self.enabled);
+        self.channel1.save_state(state);
+        self.channel2.save_state(state);
+        self.channel3.save_state(state);
+        self.channel4.save_state(state);
+        state.u8(self.frame_sequencer);
+        state.u32(self.frame_counter);
+        state.u32(self.sample_counter);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        state.bytes(&mut self.registers)?;
+        self.enabled = state.bool()?;
+        self.channel1.load_state(state)?;
+        self.channel2.load_state(state)?;
+        self.channel3.load_state(state)?;
+        self.channel4.load_state(state)?;
+        self.frame_sequencer = state.u8()? & 0x07;
+        self.frame_counter = state.u32()?;
+        self.sample_counter = state.u32()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+//Without the "audio" feature samples are produced but nobody plays them
+#[cfg(not(feature = "audio"))]
+pub struct AudioOutput;
+
+#[cfg(not(feature = "audio"))]
+pub fn start(_receiver: std::sync::mpsc::Receiver<Vec<f32>>) -> Option<AudioOutput> {
+    None
+}
+
+#[cfg(feature = "audio")]
+pub use output::start;
+
+#[cfg(feature = "audio")]
+mod output {
+    use std::collections::VecDeque;
+    use std::sync::mpsc::Receiver;
+
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use cpal::{FromSample, SizedSample};
+
+    use emul::SAMPLE_RATE;
+
+    // More than 200ms of queued sound means the emulation is ahead, drop the oldest samples
+    const MAX_QUEUED: usize = (SAMPLE_RATE as usize / 5) * 2;
+
+    pub struct AudioOutput {
+        _stream: cpal::Stream,
+    }
+
+    pub fn start(receiver: Receiver<Vec<f32>>) -> Option<AudioOutput> {
+        let device = cpal::default_host().default_output_device()?;
+        let config = match device.default_output_config() {
+            Ok(config) => config,
+            Err(e) => {
+                println!("No audio output available: {}", e);
+                return None;
+            }
+        };
+
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => build::<f32>(&device, &config.into(), receiver),
+            cpal::SampleFormat::I16 => build::<i16>(&device, &config.into(), receiver),
+            cpal::SampleFormat::U16 => build::<u16>(&device, &config.into(), receiver),
+            format => {
+                println!("Unsupported audio sample format {}", format);
+                return None;
+            }
+        }?;
+
+        if let Err(e) = stream.play() {
+            println!("Failed to start audio: {}", e);
+            return None;
+        }
+        Some(AudioOutput { _stream: stream })
+    }
+
+    fn build<T: SizedSample + FromSample<f32>>(device: &cpal::Device, config: &cpal::StreamConfig, receiver: Receiver<Vec<f32>>) -> Option<cpal::Stream> {
+        let channels = config.channels as usize;
+        //Samples are made at SAMPLE_RATE, step through them at the device rate
+        let step = f64::from(SAMPLE_RATE) / f64::from(config.sample_rate.0);
+        let mut queue: VecDeque<f32> = VecDeque::new();
+        let mut position = 0.0;
+
+        let stream = device.build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                while let Ok(samples) = receiver.try_recv() {
+                    queue.extend(samples);
+                }
+                if queue.len() > MAX_QUEUED {
+                    let excess = (queue.len() - MAX_QUEUED) & !1;
+                    queue.drain(..excess);
+                }
+
+                for frame in data.chunks_mut(channels) {
+                    let (left, right) = if queue.len() >= 2 { (queue[0], queue[1]) } else { (0.0, 0.0) };
+                    position += step;
+                    while position >= 1.0 {
+                        position -= 1.0;
+                        if queue.len() >= 2 {
+                            queue.pop_front();
+                            queue.pop_front();
+                        }
+                    }
+
+                    if channels == 1 {
+                        frame[0] = T::from_sample((left + right) / 2.0);
+                    } else {
+                        for (i, sample) in frame.iter_mut().enumerate() {
+                            *sample = T::from_sample(match i {
+                                0 => left,
+                                1 => right,
+                                _ => 0.0,
+                            });
+                        }
+                    }
+                }
+            },
+            |e| println!("Audio stream error: {}", e),
+            None,
+        );
+
+        match stream {
+            Ok(stream) => Some(stream),
+            Err(e) => {
+                println!("Failed to open audio output: {}", e);
+                None
+            }
+        }
+    }
+}
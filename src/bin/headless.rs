@@ -0,0 +1,301 @@
+//Run a rom without any window, for CI and batch runs
+//
+//  headless <rom.gb> [--frames N] [--until-pc ADDR] [--serial TEXT] [--frame-hash HASH] [--dump out.ppm] [--movie in.gbm] [--serial-stdout] [--printer DIR] [--screenshot DIR] [--screenshot-scale N] [--record-video DIR] [--video-format gif|png]
+//
+//Without any condition the rom runs for the given frames and succeeds, with conditions the
+//frames are the time limit and the first condition met stops the run.
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+
+use emul::{Diagnostic, GameBoy, Movie, SerialDevice, StdoutSink, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+const EXIT_SUCCESS: u8 = 0;
+//Frame limit reached before any condition
+const EXIT_TIMEOUT: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+//The CPU hit an illegal opcode
+const EXIT_LOCKED: u8 = 3;
+
+//One minute of emulation
+const DEFAULT_FRAMES: u32 = 3600;
+
+struct Options {
+    rom_path: String,
+    frames: u32,
+    until_pc: Option<u16>,
+    serial: Option<String>,
+    frame_hash: Option<u64>,
+    dump: Option<String>,
+    movie: Option<String>,
+    //Print the serial bytes as they are sent instead of at the end
+    serial_stdout: bool,
+    //Directory of the images printed by the Game Boy Printer
+    printer: Option<String>,
+    //Directory where the last frame is saved as a PNG
+    screenshot: Option<String>,
+    screenshot_scale: u32,
+    //Directory where every frame run is recorded
+    record_video: Option<String>,
+    //gif or png
+    video_format: String,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Options {
+            rom_path: String::new(),
+            frames: DEFAULT_FRAMES,
+            until_pc: None,
+            serial: None,
+            frame_hash: None,
+            dump: None,
+            movie: None,
+            serial_stdout: false,
+            printer: None,
+            screenshot: None,
+            screenshot_scale: 1,
+            record_video: None,
+            video_format: String::from("gif"),
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("Missing value after {}", arg));
+            match arg.as_str() {
+                "--frames" => options.frames = value()?.parse().map_err(|_| "Invalid frame count")?,
+                "--until-pc" => options.until_pc = Some(u16::from_str_radix(hex_digits(value()?), 16).map_err(|_| "Invalid PC address")?),
+                "--serial" => options.serial = Some(value()?.clone()),
+                "--frame-hash" => options.frame_hash = Some(u64::from_str_radix(hex_digits(value()?), 16).map_err(|_| "Invalid frame hash")?),
+                "--dump" => options.dump = Some(value()?.clone()),
+                "--movie" => options.movie = Some(value()?.clone()),
+                "--serial-stdout" => options.serial_stdout = true,
+                "--printer" => options.printer = Some(value()?.clone()),
+                "--screenshot" => options.screenshot = Some(value()?.clone()),
+                "--record-video" => options.record_video = Some(value()?.clone()),
+                "--video-format" => options.video_format = value()?.clone(),
+                "--screenshot-scale" => options.screenshot_scale = value()?.parse().ok().filter(|&scale| scale > 0).ok_or("Invalid screenshot scale")?,
+                _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+                _ if options.rom_path.is_empty() => options.rom_path = arg.clone(),
+                _ => return Err(format!("Unexpected argument {}", arg)),
+            }
+        }
+
+        if options.rom_path.is_empty() {
+            return Err(String::from("No path for a GB rom"));
+        }
+        if options.screenshot.is_some() && !cfg!(feature = "image") {
+            return Err(String::from("--screenshot needs the image feature"));
+        }
+        if options.record_video.is_some() && !cfg!(feature = "image") {
+            return Err(String::from("--record-video needs the image feature"));
+        }
+        if options.video_format != "gif" && options.video_format != "png" {
+            return Err(format!("Unknown video format {}, gif or png", options.video_format));
+        }
+        Ok(options)
+    }
+
+    fn has_condition(&self) -> bool {
+        self.until_pc.is_some() || self.serial.is_some() || self.frame_hash.is_some()
+    }
+}
+
+//The 0x or 0X prefix is optional
+fn hex_digits(text: &str) -> &str {
+    text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text)
+}
+
+//FNV-1a over the pixels, stable between runs and platforms
+fn frame_hash(pixels: &[u32]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in pixels.iter().flat_map(|pixel| pixel.to_le_bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+//Binary PPM, readable by most image tools without any dependency
+fn dump_frame(path: &str, pixels: &[u32]) -> std::io::Result<()> {
+    let mut data = format!("P6\n{} {}\n255\n", SCREEN_WIDTH, SCREEN_HEIGHT).into_bytes();
+    for pixel in pixels {
+        data.extend_from_slice(&[(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8]);
+    }
+    fs::write(path, data)
+}
+
+#[cfg(feature = "image")]
+fn screenshot(gameboy: &GameBoy, directory: &str, scale: u32) {
+    match emul::save_screenshot(&[gameboy.framebuffer()], &gameboy.title(), directory.as_ref(), scale) {
+        Ok(path) => println!("Screenshot saved in {}", path.display()),
+        Err(err) => eprintln!("Failed to save the screenshot in {}: {}", directory, err),
+    }
+}
+
+//Refused when the options are parsed
+#[cfg(not(feature = "image"))]
+fn screenshot(_gameboy: &GameBoy, _directory: &str, _scale: u32) {}
+
+#[cfg(feature = "image")]
+fn connect_printer(directory: &str) -> Result<Box<dyn SerialDevice>, String> {
+    fs::create_dir_all(directory).map_err(|err| format!("Error making the printer directory: {}", err))?;
+    Ok(Box::new(emul::Printer::new(directory)))
+}
+
+#[cfg(not(feature = "image"))]
+fn connect_printer(_directory: &str) -> Result<Box<dyn SerialDevice>, String> {
+    Err(String::from("--printer needs the image feature"))
+}
+
+#[cfg(feature = "image")]
+fn finish_video(recorder: emul::VideoRecorder) {
+    let path = recorder.path().to_path_buf();
+    match recorder.finish() {
+        Ok(frames) => println!("Video of {} frames written in {}", frames, path.display()),
+        Err(err) => eprintln!("Failed to write the video in {}: {}", path.display(), err),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match Options::parse(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            eprintln!("Usage: headless <rom.gb> [--frames N] [--until-pc ADDR] [--serial TEXT] [--frame-hash HASH] [--dump out.ppm] [--movie in.gbm] [--serial-stdout] [--printer DIR] [--screenshot DIR] [--screenshot-scale N] [--record-video DIR] [--video-format gif|png]");
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let bytes = match fs::read(&options.rom_path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("Error opening the file: {}", err);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let mut gameboy = GameBoy::new();
+    if let Err(err) = gameboy.load_rom(bytes) {
+        eprintln!("Error loading the rom: {}", err);
+        return ExitCode::from(EXIT_USAGE);
+    }
+    gameboy.capture_serial(true);
+    //The printer takes the port, the bytes sent are still printed at the end
+    if let Some(directory) = &options.printer {
+        match connect_printer(directory) {
+            Ok(printer) => gameboy.connect_serial(Some(printer)),
+            Err(err) => {
+                eprintln!("{}", err);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        }
+    } else if options.serial_stdout {
+        gameboy.connect_serial(Some(Box::new(StdoutSink)));
+    }
+
+    //The buttons come from the movie, none once it is over
+    let mut movie = None;
+    if let Some(path) = &options.movie {
+        let played = fs::read(path)
+            .map_err(|err| err.to_string())
+            .and_then(|data| Movie::from_bytes(&data).map_err(|err| err.to_string()))
+            .and_then(|played| played.start(&mut gameboy).map(|()| played).map_err(|err| err.to_string()));
+        match played {
+            Ok(played) => movie = Some(played),
+            Err(err) => {
+                eprintln!("Error playing the movie: {}", err);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        }
+    }
+
+    #[cfg(feature = "image")]
+    let mut video = None;
+    #[cfg(feature = "image")]
+    if let Some(directory) = &options.record_video {
+        let format = if options.video_format == "png" { emul::VideoFormat::Png } else { emul::VideoFormat::Gif };
+        match emul::VideoRecorder::start(format, &gameboy.title(), directory.as_ref()) {
+            Ok(recorder) => video = Some(recorder),
+            Err(err) => {
+                eprintln!("Error starting the video: {}", err);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        }
+    }
+
+    let mut status = if options.has_condition() { EXIT_TIMEOUT } else { EXIT_SUCCESS };
+    //Why the run stopped early, printed after the serial output
+    let mut reason = None;
+    let mut frame = 0;
+    while frame < options.frames {
+        if let Some(movie) = &movie {
+            let (buttons, turbo) = movie.frame(frame as usize).unwrap_or_default();
+            gameboy.set_buttons(buttons);
+            gameboy.set_turbo(turbo);
+        }
+        gameboy.run_frame_until(|gameboy| Some(gameboy.pc()) == options.until_pc);
+        //Samples are only needed by the video, don't let them pile up
+        let samples = gameboy.audio_samples();
+        #[cfg(feature = "image")]
+        if let Some(Err(err)) = video.as_mut().map(|recorder| recorder.push(&[gameboy.framebuffer()], &samples)) {
+            eprintln!("Video stopped: {}", err);
+            //What was written stays readable
+            if let Some(recorder) = video.take() {
+                finish_video(recorder);
+            }
+        }
+        #[cfg(not(feature = "image"))]
+        drop(samples);
+        frame += 1;
+
+        if let Some(Diagnostic::Locked { opcode, pc }) = gameboy.take_diagnostics().into_iter().next() {
+            reason = Some(format!("CPU locked by illegal opcode 0x{:02X} at 0x{:04X}", opcode, pc));
+            status = EXIT_LOCKED;
+        } else if options.until_pc.is_some_and(|pc| pc == gameboy.pc()) {
+            reason = Some(format!("PC reached 0x{:04X}", gameboy.pc()));
+            status = EXIT_SUCCESS;
+        } else if options.serial.as_ref().is_some_and(|expected| String::from_utf8_lossy(gameboy.serial_output()).contains(expected.as_str())) {
+            reason = Some(String::from("Serial output matched"));
+            status = EXIT_SUCCESS;
+        } else if options.frame_hash.is_some_and(|hash| hash == frame_hash(gameboy.framebuffer())) {
+            reason = Some(String::from("Frame hash matched"));
+            status = EXIT_SUCCESS;
+        }
+        if reason.is_some() {
+            break;
+        }
+    }
+
+    let serial = gameboy.serial_output();
+    if options.serial_stdout {
+        //Back to the start of a line after what was printed
+        if serial.last().is_some_and(|&byte| byte != b'\n') {
+            println!();
+        }
+    //The printer protocol is not text
+    } else if !serial.is_empty() && options.printer.is_none() {
+        println!("Serial output:\n{}", String::from_utf8_lossy(serial));
+    }
+    if let Some(reason) = reason {
+        println!("{}", reason);
+    }
+    println!("{} frames, frame hash {:016x}", frame, frame_hash(gameboy.framebuffer()));
+    if status == EXIT_TIMEOUT {
+        println!("Frame limit reached before any condition");
+    }
+
+    if let Some(path) = &options.dump {
+        if let Err(err) = dump_frame(path, gameboy.framebuffer()) {
+            eprintln!("Failed to dump the frame: {}", err);
+        }
+    }
+    if let Some(directory) = &options.screenshot {
+        screenshot(&gameboy, directory, options.screenshot_scale);
+    }
+    #[cfg(feature = "image")]
+    if let Some(recorder) = video {
+        finish_video(recorder);
+    }
+    ExitCode::from(status)
+}
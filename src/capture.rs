@@ -0,0 +1,225 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+
+use crate::{SAMPLE_RATE, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+//PNG of the screens side by side, each pixel drawn as a square of scale pixels. Named after
+//the rom title and the time in the directory, made if needed. Returns the path written
+pub fn save_screenshot(screens: &[&[u32]], title: &str, directory: &Path, scale: u32) -> Result<PathBuf, image::ImageError> {
+    fs::create_dir_all(directory).map_err(image::ImageError::IoError)?;
+    let path = unique_path(directory, &file_stem(title), "png");
+    save_png(&path, screens, scale)?;
+    Ok(path)
+}
+
+pub(crate) fn save_png(path: &Path, screens: &[&[u32]], scale: u32) -> Result<(), image::ImageError> {
+    let scale = scale.max(1) as usize;
+    let width = screens.len() * SCREEN_WIDTH * scale;
+    let height = SCREEN_HEIGHT * scale;
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = screens[x / (SCREEN_WIDTH * scale)][(y / scale) * SCREEN_WIDTH + (x / scale) % SCREEN_WIDTH];
+            rgb.extend_from_slice(&[(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8]);
+        }
+    }
+    image::save_buffer(path, &rgb, width as u32, height as u32, image::ColorType::Rgb8)
+}
+
+//TITLE_2026-01-31_12-00-00 with the time in UTC, the characters other than letters and
+//digits become underscores
+pub(crate) fn file_stem(title: &str) -> String {
+    let title: String = title.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    let title = if title.is_empty() { "emul" } else { &title };
+
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let (year, month, day) = civil_date(seconds / 86400);
+    let time = seconds % 86400;
+    format!("{}_{:04}-{:02}-{:02}_{:02}-{:02}-{:02}", title, year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+//Year, month and day of the days since 1970-01-01
+//https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+//Several captures in the same second get a number after the first one
+pub(crate) fn unique_path(directory: &Path, stem: &str, extension: &str) -> PathBuf {
+    let path = directory.join(format!("{}.{}", stem, extension));
+    if !path.exists() {
+        return path;
+    }
+    (2..).map(|number| directory.join(format!("{}_{}.{}", stem, number, extension)))
+        .find(|path| !path.exists())
+        .expect("a free file name")
+}
+
+//1 048 576 M-cycles per second, 17 556 per frame
+const FRAME_RATE: f64 = 1_048_576.0 / 17_556.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VideoFormat {
+    //Animated GIF, without the sound
+    Gif,
+    //A PNG per frame and a WAV of the sound in a directory, for other tools to put together
+    Png,
+}
+
+//Every frame given to it, from start to finish
+pub struct VideoRecorder {
+    path: PathBuf,
+    output: VideoOutput,
+    frames: u64,
+}
+
+enum VideoOutput {
+    Gif {
+        encoder: GifEncoder<BufWriter<File>>,
+        //Frame not written yet with the frame number it started at, the same frames
+        //following it only make it last longer
+        pending: Option<(RgbaImage, u64)>,
+    },
+    Png { wav: Wav },
+}
+
+impl VideoRecorder {
+    //Named after the rom title and the time like the screenshots, in the directory made if needed
+    pub fn start(format: VideoFormat, title: &str, directory: &Path) -> Result<Self, image::ImageError> {
+        fs::create_dir_all(directory).map_err(image::ImageError::IoError)?;
+        let stem = file_stem(title);
+        let (path, output) = match format {
+            VideoFormat::Gif => {
+                let path = unique_path(directory, &stem, "gif");
+                let file = File::create(&path).map_err(image::ImageError::IoError)?;
+                let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), 10);
+                encoder.set_repeat(Repeat::Infinite)?;
+                (path, VideoOutput::Gif { encoder, pending: None })
+            }
+            VideoFormat::Png => {
+                let path = unique_path(directory, &stem, "frames");
+                fs::create_dir(&path).map_err(image::ImageError::IoError)?;
+                let wav = Wav::create(&path.join("audio.wav")).map_err(image::ImageError::IoError)?;
+                (path, VideoOutput::Png { wav })
+            }
+        };
+        Ok(Self { path, output, frames: 0 })
+    }
+
+    //The GIF file or the directory of the frames
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    //One frame, the screens side by side, with the sound samples made during it
+    pub fn push(&mut self, screens: &[&[u32]], samples: &[f32]) -> Result<(), image::ImageError> {
+        match &mut self.output {
+            VideoOutput::Gif { encoder, pending } => {
+                let frame = rgba_image(screens);
+                //A frame coming before the pending one lasted the minimum delay is dropped
+                let frames = self.frames;
+                if pending.as_ref().is_none_or(|(previous, start)| *previous != frame && hundredths(frames) - hundredths(*start) >= MIN_DELAY) {
+                    if let Some((previous, start)) = pending.replace((frame, self.frames)) {
+                        encoder.encode_frame(gif_frame(previous, start, self.frames))?;
+                    }
+                }
+            }
+            VideoOutput::Png { wav } => {
+                save_png(&self.path.join(format!("frame_{:06}.png", self.frames)), screens, 1)?;
+                wav.push(samples).map_err(image::ImageError::IoError)?;
+            }
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    //Write what is left, returns the number of frames
+    pub fn finish(self) -> Result<u64, image::ImageError> {
+        match self.output {
+            VideoOutput::Gif { mut encoder, pending } => {
+                if let Some((previous, start)) = pending {
+                    encoder.encode_frame(gif_frame(previous, start, self.frames))?;
+                }
+            }
+            VideoOutput::Png { wav } => wav.finish().map_err(image::ImageError::IoError)?,
+        }
+        Ok(self.frames)
+    }
+}
+
+fn rgba_image(screens: &[&[u32]]) -> RgbaImage {
+    let width = screens.len() * SCREEN_WIDTH;
+    RgbaImage::from_fn(width as u32, SCREEN_HEIGHT as u32, |x, y| {
+        let (x, y) = (x as usize, y as usize);
+        let pixel = screens[x / SCREEN_WIDTH][y * SCREEN_WIDTH + x % SCREEN_WIDTH];
+        image::Rgba([(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8, 0xFF])
+    })
+}
+
+//GIF delays are in hundredths of a second, viewers slow down the ones shorter than 2 so
+//frames are dropped to make them last at least that long, leaving up to 40 per second
+const MIN_DELAY: u32 = 2;
+
+//Each delay ends where the real time rounds to, they never drift
+fn hundredths(frame: u64) -> u32 {
+    (frame as f64 * 100.0 / FRAME_RATE).round() as u32
+}
+
+fn gif_frame(image: RgbaImage, start: u64, end: u64) -> Frame {
+    let delay = (hundredths(end) - hundredths(start)).max(MIN_DELAY);
+    Frame::from_parts(image, 0, 0, Delay::from_numer_denom_ms(delay * 10, 1))
+}
+
+//16 bit stereo PCM, the sizes in the header are written at the end
+struct Wav {
+    file: BufWriter<File>,
+    data_size: u32,
+}
+
+impl Wav {
+    fn create(path: &Path) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(b"RIFF\0\0\0\0WAVEfmt ")?;
+        file.write_all(&16u32.to_le_bytes())?;
+        //PCM, 2 channels
+        file.write_all(&1u16.to_le_bytes())?;
+        file.write_all(&2u16.to_le_bytes())?;
+        file.write_all(&SAMPLE_RATE.to_le_bytes())?;
+        //Bytes per second and per sample of both channels, bits per sample
+        file.write_all(&(SAMPLE_RATE * 4).to_le_bytes())?;
+        file.write_all(&4u16.to_le_bytes())?;
+        file.write_all(&16u16.to_le_bytes())?;
+        file.write_all(b"data\0\0\0\0")?;
+        Ok(Self { file, data_size: 0 })
+    }
+
+    fn push(&mut self, samples: &[f32]) -> io::Result<()> {
+        for sample in samples {
+            self.file.write_all(&((sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16).to_le_bytes())?;
+        }
+        self.data_size += samples.len() as u32 * 2;
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file.write_all(&(36 + self.data_size).to_le_bytes())?;
+        self.file.seek(SeekFrom::Start(40))?;
+        self.file.write_all(&self.data_size.to_le_bytes())?;
+        self.file.flush()
+    }
+}
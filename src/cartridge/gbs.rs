@@ -0,0 +1,91 @@
+use crate::cartridge::MemoryBankController;
+use crate::state::{StateError, StateReader, StateWriter};
+
+//Small driver placed in the free space before the load address
+pub const DRIVER_ADDRESS: u16 = 0x0100;
+const VBLANK_VECTOR: usize = 0x40;
+const TIMER_VECTOR: usize = 0x50;
+
+//Memory map of a GBS rip: no header, the code is placed at its load address
+//and the upper bank is switched like a MBC1 (https://ocremix.org/info/GBS_Format_Specification)
+pub(crate) struct GbsRom {
+    data: Vec<u8>,
+    number_rombank: usize,
+    rombank: usize,
+    ram: Vec<u8>,
+}
+
+impl GbsRom {
+    pub fn new(code: &[u8], load_address: u16, init_address: u16, play_address: u16) -> Self {
+        let load_address = load_address as usize;
+        //Round up to a full number of 16KB banks, at least 2
+        let size = (load_address + code.len()).div_ceil(0x4000).max(2) * 0x4000;
+        let mut data = vec![0_u8; size];
+        data[load_address..load_address + code.len()].copy_from_slice(code);
+
+        //RST go to load address + RST address
+        for rst in (0x00..=0x38).step_by(8) {
+            let target = (load_address + rst) as u16;
+            data[rst..rst + 3].copy_from_slice(&[0xC3, target as u8, (target >> 8) as u8]);  // JP target
+        }
+
+        //VBlank and timer interrupts call PLAY, other interrupts just return
+        for vector in (0x40..=0x60).step_by(8) {
+            data[vector] = 0xD9;                                                                                // RETI
+        }
+        for vector in [VBLANK_VECTOR, TIMER_VECTOR] {
+            data[vector..vector + 4].copy_from_slice(&[0xCD, play_address as u8, (play_address >> 8) as u8, 0xD9]);   // CALL play; RETI
+        }
+
+        let driver = DRIVER_ADDRESS as usize;
+        data[driver..driver + 7].copy_from_slice(&[
+            0xCD, init_address as u8, (init_address >> 8) as u8,                                               // CALL init
+            0xFB,                                                                                               // EI
+            0x76,                                                                                               // HALT
+            0x18, 0xFD,                                                                                         // JR HALT
+        ]);
+
+        Self {
+            number_rombank: size / 0x4000,
+            data,
+            rombank: 1,
+            ram: vec![0_u8; 0x2000],
+        }
+    }
+}
+
+impl MemoryBankController for GbsRom {
+    fn read_byte(&self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => self.data[address as usize],
+            0x4000..=0x7FFF => self.data[(self.rombank * 0x4000) | (address as usize & 0x3FFF)],
+            0xA000..=0xBFFF => self.ram[(address & 0x1FFF) as usize],
+            _ => 0xFF,
+        }
+    }
+
+    fn write_byte(&mut self, address: u16, byte: u8) {
+        match address {
+            0x2000..=0x3FFF => {
+                let bank = if byte == 0 { 1 } else { byte as usize };
+                self.rombank = bank % self.number_rombank;
+            }
+            0xA000..=0xBFFF => self.ram[(address & 0x1FFF) as usize] = byte,
+            _ => {}
+        }
+    }
+
+    fn rom(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.u16(self.rombank as u16);
+        state.bytes(&self.ram);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.rombank = state.u16()? as usize % self.number_rombank;
+        state.bytes(&mut self.ram)
+    }
+}
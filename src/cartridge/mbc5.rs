@@ -2,7 +2,8 @@ use std::fs::File;
 use std::io::{Read, Write};
 
 use std::path::PathBuf;
-use crate::cartridge::MemoryBankController;
+use crate::cartridge::{LoadError, MemoryBankController};
+use crate::state::{StateError, StateReader, StateWriter};
 
 pub(crate) struct Mbc5{
     data:Vec<u8>,
@@ -16,17 +17,14 @@ pub(crate) struct Mbc5{
     ram_enable:bool,
     rombank: u16,
     rambank: u16,
-    path: PathBuf,
+    path: Option<PathBuf>,
 }
 
 impl Mbc5 {
-    pub fn new(bytes: Vec<u8>, cart_path: &str) -> Self {
+    pub fn new(bytes: Vec<u8>, path: Option<PathBuf>) -> Result<Self, LoadError> {
         let mut has_ram =false;
         let mut has_battery=false;
 
-        let path = PathBuf::from(cart_path);
-        let savepath= path.with_extension("gbsave");
-
         match bytes[0x147] {
             0x1A=>has_ram=true,
             0x1B=>{has_ram=true;has_battery=true},
@@ -44,7 +42,7 @@ impl Mbc5 {
         let ram = if has_ram{
             let mut data = vec![0; (0x2000 * number_rambank) as usize];
             if has_battery{
-                if let Some(save) = loadsave(savepath) {
+                if let Some(save) = path.as_ref().and_then(|path| loadsave(path.with_extension("gbsave"))) {
                     data=save;
                 } else {
                     println!("No save found or an error occured during the load.");
@@ -68,9 +66,10 @@ impl Mbc5 {
             0x52=>72,
             0x53=>80,
             0x54=>96,
-            _ => 0
+            size => return Err(LoadError::UnsupportedRomSize(size)),
         };
-        Self {
+        //Banks missing from the file read as 0xFF
+        Ok(Self {
             data: bytes,
             has_ram,
             has_battery,
@@ -81,7 +80,7 @@ impl Mbc5 {
             rombank: 1,
             rambank: 0,
             path,
-        }
+        })
     }
 
 }
@@ -96,14 +95,14 @@ impl MemoryBankController for Mbc5 {
         };
         match address{
             0x0000..=0x3FFF => {
-                self.data[address as usize]
+                self.data.get(address as usize).copied().unwrap_or(0xFF)
             }
             0x4000..=0x7FFF => {
-                self.data[address_correct]
+                self.data.get(address_correct).copied().unwrap_or(0xFF)
             }
             0xA000..=0xBFFF => {
                 if self.has_ram{
-                    self.ram[((self.rambank * 0x2000) | (address & 0x1FFF)) as usize]
+                    self.ram.get(((self.rambank * 0x2000) | (address & 0x1FFF)) as usize).copied().unwrap_or(0xFF)
                 } else {
                     0
                 }
@@ -119,27 +118,51 @@ impl MemoryBankController for Mbc5 {
             0x0000 ..= 0x1FFF => self.ram_enable = byte & 0x0F == 0x0A,
             0x2000 ..= 0x2FFF => self.rombank = ((self.rombank & 0x100) | (byte as u16)) % self.number_rombank,
             0x3000 ..= 0x3FFF => self.rombank = ((self.rombank & 0xFF) | ((byte as u16 & 1) << 8)) % self.number_rombank,
-            0x4000 ..= 0x5FFF => self.rambank = (byte as u16 & 0x0F) % self.number_rambank,
+            0x4000 ..= 0x5FFF => self.rambank = (byte as u16 & 0x0F) % self.number_rambank.max(1),
             0x6000 ..= 0x7FFF => { /* Do nothing but why don't know */ },
             0xA000..=0xBFFF => {
                 if !self.ram_enable {
                     return
                 }
 
-                self.ram[((self.rambank * 0x2000) | (address & 0x1FFF))as usize] = byte;
+                //A header with RAM but no RAM size has none
+                if let Some(ram) = self.ram.get_mut(((self.rambank * 0x2000) | (address & 0x1FFF))as usize) {
+                    *ram = byte;
+                }
             }
             _ => panic!("GG i didn't thought someone can go there if you want to know you are lost in MBC5 write_byte")
         }
     }
 
+    fn rom(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.ram_enable);
+        state.u16(self.rombank);
+        state.u16(self.rambank);
+        state.vec(&self.ram);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.ram_enable = state.bool()?;
+        self.rombank = state.u16()? % self.number_rombank;
+        self.rambank = state.u16()?;
+        if self.rambank >= self.number_rambank.max(1) {
+            return Err(StateError::Corrupt);
+        }
+        state.vec(&mut self.ram)
+    }
+
 }
 
 
 //make at the moment it destroys object
 impl Drop for Mbc5 {
     fn drop(&mut self) {
-        if self.has_ram && self.has_battery{
-            let path= self.path.with_extension("gbsave");
+        if let (true, true, Some(path)) = (self.has_ram, self.has_battery, &self.path) {
+            let path= path.with_extension("gbsave");
             File::create(path).and_then(|mut f| f.write_all(&self.ram)).expect("error saving");
 
         }
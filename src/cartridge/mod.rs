@@ -1,12 +1,58 @@
-use std::fs::File;
-use std::io::Read;
-use std::path::Path;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use crate::cartridge::rom::Rom;
 use crate::cartridge::mbc5::Mbc5;
+use crate::state::{StateError, StateReader, StateWriter};
 pub(crate) mod rom;
 pub(crate) mod mbc5;
+pub(crate) mod gbs;
 
-pub fn new(cart_path: &str) -> Box<dyn MemoryBankController> {
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    //Roms are .gb files
+    InvalidExtension,
+    //Smaller than the header
+    TooSmall,
+    //The boot rom would lock up on it
+    InvalidLogo,
+    UnsupportedCartridge(u8),
+    UnsupportedRomSize(u8),
+    //GBS music files
+    InvalidGbs,
+    UnsupportedGbsVersion(u8),
+    InvalidLoadAddress(u16),
+    NoSong,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "can't read the file: {}", err),
+            LoadError::InvalidExtension => write!(f, "invalid file extension, expected '.gb'"),
+            LoadError::TooSmall => write!(f, "invalid rom, too small to contain a header"),
+            LoadError::InvalidLogo => write!(f, "invalid rom, the Nintendo logo of the header is wrong"),
+            LoadError::UnsupportedCartridge(kind) => write!(f, "unsupported cartridge type 0x{:02X}", kind),
+            LoadError::UnsupportedRomSize(size) => write!(f, "unsupported rom size 0x{:02X}", size),
+            LoadError::InvalidGbs => write!(f, "invalid GBS file, the 'GBS' magic is missing"),
+            LoadError::UnsupportedGbsVersion(version) => write!(f, "unsupported GBS version {}", version),
+            LoadError::InvalidLoadAddress(address) => write!(f, "invalid GBS load address 0x{:04X}", address),
+            LoadError::NoSong => write!(f, "GBS file without any song"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+pub fn new(cart_path: &str) -> Result<Box<dyn MemoryBankController>, LoadError> {
 
     // Check if the file has the ".gb" extension
     let file_extension = Path::new(cart_path)
@@ -14,20 +60,23 @@ pub fn new(cart_path: &str) -> Box<dyn MemoryBankController> {
         .and_then(|ext| ext.to_str());
 
     if file_extension != Some("gb") {
-        panic!("Invalid file extension. Expected '.gb'");
+        return Err(LoadError::InvalidExtension);
+    }
+
+    let bytes = fs::read(cart_path)?;
+    from_bytes(bytes, Some(PathBuf::from(cart_path)))
+}
+
+//Battery backed RAM is only saved next to the rom when there is a path
+pub fn from_bytes(bytes: Vec<u8>, cart_path: Option<PathBuf>) -> Result<Box<dyn MemoryBankController>, LoadError> {
+    if bytes.len() < 0x150 {
+        return Err(LoadError::TooSmall);
     }
 
-    let mut input_file = match File::open(cart_path) {
-        Ok(file) => file,
-        Err(err) => {
-            panic!("Error opening the file: {}", err);
-        }
-    };
-    let mut bytes : Vec<u8> = vec![];
-    input_file.read_to_end(&mut bytes).expect("read bytes from file");
-    
     //check nintendo logo
-    assert_eq!(&bytes[0x104..0x134], &[0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D, 0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99, 0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E]);
+    if bytes[0x104..0x134] != [0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D, 0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99, 0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E] {
+        return Err(LoadError::InvalidLogo);
+    }
     
     if calculate_checksum(&bytes) != bytes[0x014D] {
         // The checksum doesn't match, handle the error accordingly
@@ -37,14 +86,14 @@ pub fn new(cart_path: &str) -> Box<dyn MemoryBankController> {
         println!("Checksum is valid.");
     }
     
-    match bytes[0x147] {
+    Ok(match bytes[0x147] {
         0x00 => Box::new(Rom::new(bytes)),
         //0x01..=0x03 => Box::new(Mbc1::with_ram_and_battery(0,bytes, 0x2000)),
         //0x05..0x06 => Box::new(Mbc2::new())
         //0x08..0x09 => Box::new(Rom::new())  //rom with ram and battery docs says not known
         //0x0B..0x0D => Box::new(Mmm01::new())
         //0x0F..0x13 => Box::new(Mbc3::new())
-        0x19..=0x1B => Box::new(Mbc5::new(bytes,cart_path)),  // 1B for pokemon red
+        0x19..=0x1B => Box::new(Mbc5::new(bytes, cart_path)?),  // 1B for pokemon red
         //0x1C..=0x1E => Box::new(Mbc5::new(bytes)),  //rumble
         //0x20 => Box::new(Mbc6::new())
         //0x22 => Box::new(Mbc7::new())
@@ -52,8 +101,8 @@ pub fn new(cart_path: &str) -> Box<dyn MemoryBankController> {
         //0xFD => Box::new(BandaiTama::new())
         //0xFE => Box::new(HuC3::new())
         //0xFF => Box::new(HuC1::new())
-        _ => panic!("Type of game not recognize")
-    }
+        kind => return Err(LoadError::UnsupportedCartridge(kind)),
+    })
 }
 
 
@@ -72,4 +121,12 @@ pub trait MemoryBankController : Send {
     fn read_byte(&self, address: u16) -> u8;
 
     fn write_byte(&mut self, address: u16,byte: u8);
+
+    //Whole rom, used to check a save state belongs to it
+    fn rom(&self) -> &[u8];
+
+    //Bank registers and RAM, the rom itself is not saved
+    fn save_state(&self, state: &mut StateWriter);
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError>;
 }
@@ -1,4 +1,5 @@
 use crate::cartridge::MemoryBankController;
+use crate::state::{StateError, StateReader, StateWriter};
 
 pub struct Rom {
     data: Vec<u8>,
@@ -13,13 +14,25 @@ impl Rom {
 }
 
 impl MemoryBankController for Rom {
+    //There is no RAM, 0xA000-0xBFFF and anything past a short rom read 0xFF
     fn read_byte(&self, address: u16) -> u8{
-        self.data[address as usize]
+        self.data.get(address as usize).copied().unwrap_or(0xFF)
     }
 
     fn write_byte(&mut self, _address: u16,_byte: u8){
         //no writing right in rom only
     }
+
+    fn rom(&self) -> &[u8] {
+        &self.data
+    }
+
+    //Nothing can change in a rom only cartridge
+    fn save_state(&self, _state: &mut StateWriter) {}
+
+    fn load_state(&mut self, _state: &mut StateReader) -> Result<(), StateError> {
+        Ok(())
+    }
 }
 impl Drop for Rom {
     fn drop(&mut self) {
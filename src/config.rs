@@ -0,0 +1,314 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use emul::KeyType;
+use winit::event::VirtualKeyCode;
+
+//Everything a key can do
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Button(KeyType),
+    //Pressed and released every few frames while held
+    Turbo(KeyType),
+    Rewind,
+    Pause,
+    FrameAdvance,
+    FastForward,
+    SpeedUp,
+    SpeedDown,
+    SaveState(u8),
+    LoadState(u8),
+    //Music files only
+    NextTrack,
+    PreviousTrack,
+    //Two Game Boys only, the controls go to the other one
+    SwitchPlayer,
+    Screenshot,
+    RecordVideo,
+}
+
+//Names in the config file, in the order the mapping is printed
+const ACTIONS: [(&str, Action); 35] = [
+    ("up", Action::Button(KeyType::Up)),
+    ("down", Action::Button(KeyType::Down)),
+    ("left", Action::Button(KeyType::Left)),
+    ("right", Action::Button(KeyType::Right)),
+    ("a", Action::Button(KeyType::A)),
+    ("b", Action::Button(KeyType::B)),
+    ("select", Action::Button(KeyType::Select)),
+    ("start", Action::Button(KeyType::Start)),
+    ("turbo_up", Action::Turbo(KeyType::Up)),
+    ("turbo_down", Action::Turbo(KeyType::Down)),
+    ("turbo_left", Action::Turbo(KeyType::Left)),
+    ("turbo_right", Action::Turbo(KeyType::Right)),
+    ("turbo_a", Action::Turbo(KeyType::A)),
+    ("turbo_b", Action::Turbo(KeyType::B)),
+    ("turbo_select", Action::Turbo(KeyType::Select)),
+    ("turbo_start", Action::Turbo(KeyType::Start)),
+    ("rewind", Action::Rewind),
+    ("pause", Action::Pause),
+    ("frame_advance", Action::FrameAdvance),
+    ("fast_forward", Action::FastForward),
+    ("speed_up", Action::SpeedUp),
+    ("speed_down", Action::SpeedDown),
+    ("save_state_1", Action::SaveState(1)),
+    ("save_state_2", Action::SaveState(2)),
+    ("save_state_3", Action::SaveState(3)),
+    ("save_state_4", Action::SaveState(4)),
+    ("load_state_1", Action::LoadState(1)),
+    ("load_state_2", Action::LoadState(2)),
+    ("load_state_3", Action::LoadState(3)),
+    ("load_state_4", Action::LoadState(4)),
+    ("next_track", Action::NextTrack),
+    ("previous_track", Action::PreviousTrack),
+    ("switch_player", Action::SwitchPlayer),
+    ("screenshot", Action::Screenshot),
+    ("record_video", Action::RecordVideo),
+];
+
+//Same format as the config file, which only needs the actions it changes
+const DEFAULT_CONFIG: &str = "
+[keyboard]
+up = Up
+down = Down
+left = Left
+right = Right
+a = Q
+b = S
+select = W
+start = X
+turbo_a = A
+turbo_b = Z
+rewind = Back
+pause = P
+frame_advance = N
+fast_forward = Tab
+speed_up = Equals, Plus, NumpadAdd
+speed_down = Minus, NumpadSubtract
+save_state_1 = F1
+save_state_2 = F2
+save_state_3 = F3
+save_state_4 = F4
+load_state_1 = Shift+F1
+load_state_2 = Shift+F2
+load_state_3 = Shift+F3
+load_state_4 = Shift+F4
+next_track = Right
+previous_track = Left
+switch_player = F5
+screenshot = F12
+record_video = F10
+
+[gamepad]
+up = DPadUp
+down = DPadDown
+left = DPadLeft
+right = DPadRight
+a = East
+b = South
+select = Select
+start = Start
+turbo_a = North
+turbo_b = West
+rewind = LeftTrigger
+fast_forward = RightTrigger
+";
+
+//How far the left stick moves before it presses a direction, from 0 to 1
+#[cfg(feature = "gamepad")]
+const DEFAULT_DEADZONE: f32 = 0.5;
+
+macro_rules! names {
+    ($type:ident: $($name:ident),* $(,)?) => {
+        &[$((stringify!($name), $type::$name)),*]
+    };
+}
+
+//Keys that can be bound, named like winit's VirtualKeyCode
+const KEY_NAMES: &[(&str, VirtualKeyCode)] = names!(VirtualKeyCode:
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Up, Down, Left, Right, Space, Return, Back, Tab, Escape, Insert, Delete, Home, End, PageUp, PageDown,
+    LShift, RShift, LControl, RControl, LAlt, RAlt,
+    Minus, Equals, Plus, Comma, Period, Slash, Backslash, Semicolon, Colon, Apostrophe, Grave, LBracket, RBracket,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    NumpadAdd, NumpadSubtract, NumpadMultiply, NumpadDivide, NumpadDecimal, NumpadEnter,
+);
+
+//Gamepad buttons, named like gilrs' Button, South is the bottom face button
+#[cfg(feature = "gamepad")]
+const BUTTON_NAMES: &[(&str, gilrs::Button)] = {
+    use gilrs::Button;
+    names!(Button:
+        South, East, North, West, C, Z, LeftTrigger, LeftTrigger2, RightTrigger, RightTrigger2,
+        Select, Start, Mode, LeftThumb, RightThumb, DPadUp, DPadDown, DPadLeft, DPadRight,
+    )
+};
+
+//A key, with Shift held or not
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Binding {
+    key: VirtualKeyCode,
+    shift: bool,
+}
+
+impl Binding {
+    fn parse(text: &str) -> Option<Self> {
+        let (shift, name) = match text.split_once('+') {
+            Some((modifier, name)) if modifier.trim().eq_ignore_ascii_case("shift") => (true, name.trim()),
+            _ => (false, text),
+        };
+        KEY_NAMES.iter()
+            .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+            .map(|&(_, key)| Binding { key, shift })
+    }
+
+    fn name(&self) -> String {
+        let name = KEY_NAMES.iter().find(|(_, key)| *key == self.key).map_or("?", |(name, _)| name);
+        if self.shift { format!("Shift+{}", name) } else { name.to_string() }
+    }
+}
+
+//Keys of every action, a key can do several things and an action can have several keys
+pub struct Bindings {
+    keys: Vec<(Binding, Action)>,
+    #[cfg(feature = "gamepad")]
+    buttons: Vec<(gilrs::Button, Action)>,
+    #[cfg(feature = "gamepad")]
+    pub deadzone: f32,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let mut bindings = Bindings {
+            keys: Vec::new(),
+            #[cfg(feature = "gamepad")]
+            buttons: Vec::new(),
+            #[cfg(feature = "gamepad")]
+            deadzone: DEFAULT_DEADZONE,
+        };
+        bindings.apply(DEFAULT_CONFIG, "default config");
+        bindings
+    }
+}
+
+//emul/config.ini in the config directory of the user
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("emul").join("config.ini"))
+}
+
+impl Bindings {
+    //The defaults changed by the file, a missing file keeps them all
+    pub fn load(path: &Path) -> Self {
+        let mut bindings = Bindings::default();
+        match fs::read_to_string(path) {
+            Ok(text) => bindings.apply(&text, &path.display().to_string()),
+            Err(err) if err.kind() == ErrorKind::NotFound => (),
+            Err(err) => println!("Failed to read {}: {}", path.display(), err),
+        }
+        bindings
+    }
+
+    //INI lines, an action given in the text loses its previous keys, errors only skip the line
+    fn apply(&mut self, text: &str, origin: &str) {
+        let mut section = String::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                section = name.trim().to_lowercase();
+                continue;
+            }
+            //Without the feature the gamepad section is ignored
+            if section != "keyboard" && !(cfg!(feature = "gamepad") && section == "gamepad") {
+                continue;
+            }
+
+            let Some((name, keys)) = line.split_once('=') else {
+                println!("{}:{}: expected 'action = keys'", origin, number + 1);
+                continue;
+            };
+            let name = name.trim();
+            #[cfg(feature = "gamepad")]
+            if section == "gamepad" && name == "deadzone" {
+                match keys.trim().parse() {
+                    Ok(deadzone) if (0.0..1.0).contains(&deadzone) => self.deadzone = deadzone,
+                    _ => println!("{}:{}: the deadzone goes from 0 to 1", origin, number + 1),
+                }
+                continue;
+            }
+            let Some((_, action)) = ACTIONS.into_iter().find(|(action_name, _)| *action_name == name) else {
+                println!("{}:{}: unknown action '{}'", origin, number + 1, name);
+                continue;
+            };
+            let keys = keys.split(',').map(str::trim).filter(|key| !key.is_empty());
+
+            #[cfg(feature = "gamepad")]
+            if section == "gamepad" {
+                self.buttons.retain(|(_, bound)| *bound != action);
+                for key in keys {
+                    match BUTTON_NAMES.iter().find(|(button_name, _)| button_name.eq_ignore_ascii_case(key)) {
+                        Some(&(_, button)) => self.buttons.push((button, action)),
+                        None => println!("{}:{}: unknown button '{}'", origin, number + 1, key),
+                    }
+                }
+                continue;
+            }
+
+            self.keys.retain(|(_, bound)| *bound != action);
+            for key in keys {
+                match Binding::parse(key) {
+                    Some(binding) => self.keys.push((binding, action)),
+                    None => println!("{}:{}: unknown key '{}'", origin, number + 1, key),
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn button_actions(&self, button: gilrs::Button) -> impl Iterator<Item = Action> + '_ {
+        self.buttons.iter().filter(move |(bound, _)| *bound == button).map(|&(_, action)| action)
+    }
+
+    //Shift bindings win while Shift is held, otherwise the key alone counts
+    pub fn actions(&self, key: VirtualKeyCode, shift: bool) -> Vec<Action> {
+        let matching = |with_shift: bool| self.keys.iter()
+            .filter(|(binding, _)| binding.key == key && binding.shift == with_shift)
+            .map(|&(_, action)| action)
+            .collect::<Vec<_>>();
+        let actions = if shift { matching(true) } else { Vec::new() };
+        if actions.is_empty() { matching(false) } else { actions }
+    }
+
+    //The whole mapping in the config file format
+    pub fn describe(&self) -> String {
+        let mut text = String::from("[keyboard]\n");
+        for (name, action) in ACTIONS {
+            let keys: Vec<String> = self.keys.iter()
+                .filter(|(_, bound)| *bound == action)
+                .map(|(binding, _)| binding.name())
+                .collect();
+            text.push_str(format!("{} = {}", name, keys.join(", ")).trim_end());
+            text.push('\n');
+        }
+
+        #[cfg(feature = "gamepad")]
+        {
+            text.push_str("\n[gamepad]\n");
+            for (name, action) in ACTIONS {
+                let buttons: Vec<&str> = self.buttons.iter()
+                    .filter(|(_, bound)| *bound == action)
+                    .filter_map(|(button, _)| BUTTON_NAMES.iter().find(|(_, named)| named == button).map(|(name, _)| *name))
+                    .collect();
+                text.push_str(format!("{} = {}", name, buttons.join(", ")).trim_end());
+                text.push('\n');
+            }
+            text.push_str(&format!("deadzone = {}\n", self.deadzone));
+        }
+        text
+    }
+}
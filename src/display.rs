@@ -0,0 +1,41 @@
+use std::num::NonZeroU32;
+
+use glium::glutin;
+use glium::glutin::display::GetGlDisplay;
+use glium::glutin::prelude::*;
+use glium::glutin::surface::{SwapInterval, WindowSurface};
+use glium::Display;
+use raw_window_handle::HasRawWindowHandle;
+
+//Same as glium's SimpleWindowBuilder, which can't choose the swap interval
+pub fn build(event_loop: &winit::event_loop::EventLoop<()>, title: &str, width: u32, height: u32, vsync: bool) -> (winit::window::Window, Display<WindowSurface>) {
+    let window_builder = winit::window::WindowBuilder::new()
+        .with_title(title)
+        .with_inner_size(winit::dpi::PhysicalSize::new(width, height));
+    let (window, gl_config) = glutin_winit::DisplayBuilder::new()
+        .with_window_builder(Some(window_builder))
+        .build(event_loop, glutin::config::ConfigTemplateBuilder::new(), |mut configs| configs.next().unwrap())
+        .unwrap();
+    let window = window.unwrap();
+
+    let (width, height): (u32, u32) = window.inner_size().into();
+    let attributes = glutin::surface::SurfaceAttributesBuilder::<WindowSurface>::new().build(
+        window.raw_window_handle(),
+        NonZeroU32::new(width).unwrap(),
+        NonZeroU32::new(height).unwrap(),
+    );
+    let surface = unsafe { gl_config.display().create_window_surface(&gl_config, &attributes).unwrap() };
+    let context_attributes = glutin::context::ContextAttributesBuilder::new().build(Some(window.raw_window_handle()));
+    let context = unsafe { gl_config.display().create_context(&gl_config, &context_attributes).expect("failed to create context") }
+        .make_current(&surface)
+        .unwrap();
+
+    //With vsync finishing a frame waits for the display
+    let interval = if vsync { SwapInterval::Wait(NonZeroU32::new(1).unwrap()) } else { SwapInterval::DontWait };
+    if let Err(e) = surface.set_swap_interval(&context, interval) {
+        println!("Failed to set the swap interval: {}", e);
+    }
+
+    let display = Display::from_context_surface(context, surface).unwrap();
+    (window, display)
+}
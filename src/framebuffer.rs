@@ -0,0 +1,77 @@
+use std::sync::{Arc, Mutex};
+
+use emul::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+//RGBA8, the layout the texture is uploaded from. Several screens are side by side
+pub type Frame = [u8];
+
+//Triple buffering: the emulation draws in its own buffer and swaps it with the middle one,
+//the window swaps the middle one with its own when it is newer. Only pointers are swapped
+//under the lock, neither side waits for the other and old frames are simply overwritten.
+struct Middle {
+    frame: Box<Frame>,
+    //Written since the window last took it
+    fresh: bool,
+}
+
+pub struct FrameWriter {
+    back: Box<Frame>,
+    screens: usize,
+    middle: Arc<Mutex<Middle>>,
+}
+
+pub struct FrameReader {
+    front: Box<Frame>,
+    middle: Arc<Mutex<Middle>>,
+}
+
+pub fn triple_buffer(screens: usize) -> (FrameWriter, FrameReader) {
+    let middle = Arc::new(Mutex::new(Middle { frame: blank(screens), fresh: false }));
+    (
+        FrameWriter { back: blank(screens), screens, middle: middle.clone() },
+        FrameReader { front: blank(screens), middle },
+    )
+}
+
+fn blank(screens: usize) -> Box<Frame> {
+    vec![0xFF; screens * SCREEN_WIDTH * SCREEN_HEIGHT * 4].into_boxed_slice()
+}
+
+impl FrameWriter {
+    //Convert the 0xRRGGBB pixels of the core and hand the frame to the window, one screen
+    //for each given when the buffer was made, from left to right
+    pub fn publish(&mut self, screens: &[&[u32]]) {
+        assert_eq!(screens.len(), self.screens, "one framebuffer per screen");
+        let line_width = self.screens * SCREEN_WIDTH * 4;
+        for (index, pixels) in screens.iter().enumerate() {
+            for (line, pixels) in pixels.chunks_exact(SCREEN_WIDTH).enumerate() {
+                let start = line * line_width + index * SCREEN_WIDTH * 4;
+                for (rgba, pixel) in self.back[start..start + SCREEN_WIDTH * 4].chunks_exact_mut(4).zip(pixels) {
+                    rgba.copy_from_slice(&[(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8, 0xFF]);
+                }
+            }
+        }
+        let mut middle = self.middle.lock().unwrap();
+        std::mem::swap(&mut self.back, &mut middle.frame);
+        middle.fresh = true;
+    }
+}
+
+impl FrameReader {
+    //The newest frame, None when nothing was published since the last call
+    pub fn latest(&mut self) -> Option<&Frame> {
+        let mut middle = self.middle.lock().unwrap();
+        if !middle.fresh {
+            return None;
+        }
+        std::mem::swap(&mut self.front, &mut middle.frame);
+        middle.fresh = false;
+        drop(middle);
+        Some(&self.front)
+    }
+
+    //The emulation thread dropped its writer
+    pub fn is_closed(&self) -> bool {
+        Arc::strong_count(&self.middle) == 1
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use emul::KeyType;
+use gilrs::{Axis, Button, EventType, GamepadId, Gilrs};
+
+use crate::config::{Action, Bindings};
+
+//Game controllers, turned into the same actions as the keyboard
+pub struct Gamepads {
+    gilrs: Gilrs,
+    //What each gamepad holds, released when it is unplugged
+    held: HashMap<GamepadId, Held>,
+}
+
+#[derive(Default)]
+struct Held {
+    buttons: Vec<Button>,
+    //Directions pressed by the left stick
+    stick: Vec<KeyType>,
+}
+
+impl Gamepads {
+    //None when the platform has no gamepad support, the keyboard still works
+    pub fn new() -> Option<Self> {
+        match Gilrs::new() {
+            Ok(gilrs) => {
+                for (_, gamepad) in gilrs.gamepads() {
+                    println!("Gamepad connected: {}", gamepad.name());
+                }
+                Some(Self { gilrs, held: HashMap::new() })
+            }
+            Err(err) => {
+                println!("Gamepads unavailable: {}", err);
+                None
+            }
+        }
+    }
+
+    //Actions since the last call, with true when pressed and false when released
+    pub fn poll(&mut self, bindings: &Bindings) -> Vec<(Action, bool)> {
+        let mut actions = Vec::new();
+        while let Some(event) = self.gilrs.next_event() {
+            let held = self.held.entry(event.id).or_default();
+            match event.event {
+                EventType::ButtonPressed(button, _) if !held.buttons.contains(&button) => {
+                    held.buttons.push(button);
+                    actions.extend(bindings.button_actions(button).map(|action| (action, true)));
+                }
+                EventType::ButtonReleased(button, _) if held.buttons.contains(&button) => {
+                    held.buttons.retain(|held| *held != button);
+                    actions.extend(bindings.button_actions(button).map(|action| (action, false)));
+                }
+                //Up is positive on the Y axis
+                EventType::AxisChanged(Axis::LeftStickX, value, _) => {
+                    held.stick_direction(KeyType::Left, value < -bindings.deadzone, &mut actions);
+                    held.stick_direction(KeyType::Right, value > bindings.deadzone, &mut actions);
+                }
+                EventType::AxisChanged(Axis::LeftStickY, value, _) => {
+                    held.stick_direction(KeyType::Down, value < -bindings.deadzone, &mut actions);
+                    held.stick_direction(KeyType::Up, value > bindings.deadzone, &mut actions);
+                }
+                EventType::Connected => println!("Gamepad connected: {}", self.gilrs.gamepad(event.id).name()),
+                EventType::Disconnected => {
+                    println!("Gamepad disconnected: {}", self.gilrs.gamepad(event.id).name());
+                    if let Some(held) = self.held.remove(&event.id) {
+                        for button in held.buttons {
+                            actions.extend(bindings.button_actions(button).map(|action| (action, false)));
+                        }
+                        actions.extend(held.stick.into_iter().map(|key_type| (Action::Button(key_type), false)));
+                    }
+                }
+                _ => (),
+            }
+        }
+        actions
+    }
+}
+
+impl Held {
+    //Press or release the direction when the stick crosses the deadzone
+    fn stick_direction(&mut self, key_type: KeyType, pressed: bool, actions: &mut Vec<(Action, bool)>) {
+        if pressed == self.stick.contains(&key_type) {
+            return;
+        }
+        if pressed {
+            self.stick.push(key_type);
+        } else {
+            self.stick.retain(|held| *held != key_type);
+        }
+        actions.push((Action::Button(key_type), pressed));
+    }
+}
@@ -0,0 +1,123 @@
+use crate::apu::sound::Apu;
+use crate::cartridge::gbs::{GbsRom, DRIVER_ADDRESS};
+use crate::cartridge::LoadError;
+use crate::GameBoy;
+
+//https://ocremix.org/info/GBS_Format_Specification
+const HEADER_SIZE: usize = 0x70;
+
+pub struct GbsHeader {
+    pub song_count: u8,
+    pub first_song: u8,
+    pub load_address: u16,
+    pub init_address: u16,
+    pub play_address: u16,
+    pub stack_pointer: u16,
+    pub timer_modulo: u8,
+    pub timer_control: u8,
+    pub title: String,
+    pub author: String,
+    pub copyright: String,
+}
+
+impl GbsHeader {
+    pub fn parse(bytes: &[u8]) -> Result<Self, LoadError> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..3] != b"GBS" {
+            return Err(LoadError::InvalidGbs);
+        }
+        if bytes[0x03] != 1 {
+            return Err(LoadError::UnsupportedGbsVersion(bytes[0x03]));
+        }
+
+        let word = |offset: usize| u16::from(bytes[offset]) | (u16::from(bytes[offset + 1]) << 8);
+        //Strings are 32 bytes padded with 0
+        let text = |offset: usize| {
+            let field = &bytes[offset..offset + 32];
+            let end = field.iter().position(|&c| c == 0).unwrap_or(32);
+            String::from_utf8_lossy(&field[..end]).into_owned()
+        };
+
+        let header = Self {
+            song_count: bytes[0x04],
+            first_song: bytes[0x05],
+            load_address: word(0x06),
+            init_address: word(0x08),
+            play_address: word(0x0A),
+            stack_pointer: word(0x0C),
+            timer_modulo: bytes[0x0E],
+            timer_control: bytes[0x0F],
+            title: text(0x10),
+            author: text(0x30),
+            copyright: text(0x50),
+        };
+
+        //Below 0x400 the code would overwrite the driver and the interrupt vectors
+        if header.load_address < 0x0400 || header.load_address >= 0x8000 {
+            return Err(LoadError::InvalidLoadAddress(header.load_address));
+        }
+        if header.song_count == 0 {
+            return Err(LoadError::NoSong);
+        }
+        Ok(header)
+    }
+
+    pub fn uses_timer(&self) -> bool {
+        self.timer_control & 0x04 != 0
+    }
+}
+
+pub struct GbsPlayer {
+    gameboy: GameBoy,
+    pub header: GbsHeader,
+    code: Vec<u8>,
+}
+
+impl GbsPlayer {
+    pub fn new(mut bytes: Vec<u8>) -> Result<Self, LoadError> {
+        let header = GbsHeader::parse(&bytes)?;
+        let code = bytes.split_off(HEADER_SIZE);
+
+        let cartridge = Box::new(GbsRom::new(&code, header.load_address, header.init_address, header.play_address));
+        let mut player = Self {
+            gameboy: GameBoy::with_cartridge(cartridge),
+            header,
+            code,
+        };
+        player.play(player.header.first_song.saturating_sub(1));
+        Ok(player)
+    }
+
+    //Restart the machine and call INIT with the track (0 based) in A
+    pub fn play(&mut self, track: u8) {
+        let header = &self.header;
+        let track = track % header.song_count;
+
+        let cpu = &mut self.gameboy.cpu;
+        let bus = &mut cpu.bus;
+        bus.rom = Box::new(GbsRom::new(&self.code, header.load_address, header.init_address, header.play_address));
+        bus.wram = [0_u8; 0x2000];
+        bus.hram = [0_u8; 0x80];
+        bus.apu = Apu::new();
+        bus.write_byte(0xFF06, header.timer_modulo);
+        bus.write_byte(0xFF07, header.timer_control);
+        //PLAY is called from the timer interrupt if requested, VBlank otherwise
+        bus.interrupt_enabled = if header.uses_timer() { 0x04 } else { 0x01 };
+        bus.interrupt_flags = 0;
+
+        cpu.registers.a = track;
+        cpu.sp = header.stack_pointer;
+        cpu.pc = DRIVER_ADDRESS;
+        cpu.interrupt_master_enable = false;
+        cpu.halt = false;
+        cpu.halt_bug = false;
+        cpu.stopped = false;
+        cpu.locked = false;
+        cpu.ei = false;
+    }
+
+    //The machine playing the music, the screen stays whatever INIT and PLAY draw
+    pub fn gameboy(&mut self) -> &mut GameBoy {
+        &mut self.gameboy
+    }
+}
+
@@ -1,6 +1,9 @@
-use std::sync::mpsc;
-
+use crate::state::{StateError, StateReader, StateWriter};
 
+//Frames a turbo button stays pressed, then as many released
+pub const DEFAULT_TURBO_RATE: u8 = 2;
+//Twice the rate still fits the frame counter
+pub const MAX_TURBO_RATE: u8 = 127;
 
 pub struct Input {
     up: Key,
@@ -11,9 +14,17 @@ pub struct Input {
     b: Key,
     select: Key,
     start: Key,
+    //Bits 4 and 5 of P1, a row is selected when its bit is low
     pub(crate) input_reg: u8,
     pub interrupt: u8,
-    key_data_receiver: mpsc::Receiver<Key>,
+    //A real D-pad can't press both, some games break when it happens
+    pub(crate) allow_opposite: bool,
+    //Buttons given by the front end, the keys above are what the game sees
+    held: Buttons,
+    turbo: Buttons,
+    pub(crate) turbo_rate: u8,
+    //Frames since a turbo button was pressed with none held, modulo twice the rate
+    turbo_counter: u8,
 }
 
 pub struct Key {
@@ -21,7 +32,58 @@ pub struct Key {
     pub is_down: bool,
 }
 
-#[derive(Debug)]
+//State of the eight buttons, true when held
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Buttons {
+    pub right: bool,
+    pub left: bool,
+    pub up: bool,
+    pub down: bool,
+    pub a: bool,
+    pub b: bool,
+    pub select: bool,
+    pub start: bool,
+}
+
+impl Buttons {
+    pub fn set(&mut self, key: &Key) {
+        let button = match key.key_type {
+            KeyType::Right => &mut self.right,
+            KeyType::Left => &mut self.left,
+            KeyType::Up => &mut self.up,
+            KeyType::Down => &mut self.down,
+            KeyType::A => &mut self.a,
+            KeyType::B => &mut self.b,
+            KeyType::Select => &mut self.select,
+            KeyType::Start => &mut self.start,
+        };
+        *button = key.is_down;
+    }
+
+    //A, B, Select, Start in the low bits then Right, Left, Up, Down, the P1 order
+    pub fn to_byte(self) -> u8 {
+        [self.a, self.b, self.select, self.start, self.right, self.left, self.up, self.down]
+            .iter()
+            .enumerate()
+            .fold(0, |byte, (bit, &held)| byte | (held as u8) << bit)
+    }
+
+    pub fn from_byte(byte: u8) -> Self {
+        let held = |bit: u8| byte & (1 << bit) != 0;
+        Buttons {
+            a: held(0),
+            b: held(1),
+            select: held(2),
+            start: held(3),
+            right: held(4),
+            left: held(5),
+            up: held(6),
+            down: held(7),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum KeyType {
     Right,
     Left,
@@ -41,18 +103,8 @@ macro_rules! key {
         }
     };
 }
-macro_rules!  handle_key_event {
-    ($self:ident, $key_field:ident, $key_type:ident, $key_data:ident) => {
-        if $self.$key_field.is_down == $key_data.is_down {
-            false
-        } else {
-            $self.$key_field.is_down = $key_data.is_down;
-            true
-        }
-    };
-}
 impl Input {
-    pub fn new(key_data_receiver: mpsc::Receiver<Key>) -> Self {
+    pub fn new() -> Self {
         Self {
             right: key!(Right),
             left: key!(Left),
@@ -64,67 +116,130 @@ impl Input {
             start: key!(Start),
             input_reg: 0,
             interrupt: 0,
-            key_data_receiver,
+            allow_opposite: false,
+            held: Buttons::default(),
+            turbo: Buttons::default(),
+            turbo_rate: DEFAULT_TURBO_RATE,
+            turbo_counter: 0,
         }
     }
 
+    //Bits 6 and 7 are not wired and read as 1
+    //https://gbdev.io/pandocs/Joypad_Input.html
     pub fn read(&self) -> u8 {
-        self.input_reg
+        0xC0 | self.input_reg | self.lines()
     }
 
     pub fn write(&mut self, value: u8) {
-        self.input_reg = value;
-        self.update_input_reg();
-    }
-
-    pub fn run(&mut self) {
-        if let Ok(key) = self.key_data_receiver.try_recv() {
-            let changed = match key.key_type {
-                KeyType::Up => handle_key_event!(self, up, Up, key),
-                KeyType::Down => handle_key_event!(self, down, Down, key),
-                KeyType::Left => handle_key_event!(self, left, Left, key),
-                KeyType::Right => handle_key_event!(self, right, Right, key),
-                KeyType::A => handle_key_event!(self, a, A, key),
-                KeyType::B => handle_key_event!(self, b, B, key),
-                KeyType::Select => handle_key_event!(self, select, Select, key),
-                KeyType::Start => handle_key_event!(self, start, Start, key),
-            };
-
-            if changed {
-                if key.is_down {
-                    self.interrupt |= 0x10;
-                }
-                self.update_input_reg();
+        let before = self.lines();
+        self.input_reg = value & 0x30;
+        self.line_changes(before);
+    }
+
+    //All the buttons of a frame at once, the interrupt only sees the final state
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        self.held = buttons;
+        self.apply_buttons();
+    }
+
+    //Buttons pressed and released repeatedly while they are held
+    pub fn set_turbo(&mut self, turbo: Buttons) {
+        //The first press starts pressed whatever the frame
+        if self.turbo.to_byte() == 0 && turbo.to_byte() != 0 {
+            self.turbo_counter = 0;
+        }
+        self.turbo = turbo;
+        self.apply_buttons();
+    }
+
+    //Called after every frame, the turbo buttons toggle on frame boundaries only
+    pub fn end_frame(&mut self) {
+        self.turbo_counter = (self.turbo_counter + 1) % (2 * self.turbo_rate.max(1));
+        self.apply_buttons();
+    }
+
+    fn apply_buttons(&mut self) {
+        let mut buttons = self.held;
+        if self.turbo_counter < self.turbo_rate {
+            buttons = Buttons::from_byte(buttons.to_byte() | self.turbo.to_byte());
+        }
+        if !self.allow_opposite {
+            if buttons.left && buttons.right {
+                buttons.left = false;
+                buttons.right = false;
             }
+            if buttons.up && buttons.down {
+                buttons.up = false;
+                buttons.down = false;
+            }
+        }
+
+        let before = self.lines();
+        for (key, is_down) in [
+            (&mut self.right, buttons.right),
+            (&mut self.left, buttons.left),
+            (&mut self.up, buttons.up),
+            (&mut self.down, buttons.down),
+            (&mut self.a, buttons.a),
+            (&mut self.b, buttons.b),
+            (&mut self.select, buttons.select),
+            (&mut self.start, buttons.start),
+        ] {
+            key.is_down = is_down;
         }
+        self.line_changes(before);
     }
 
-    fn update_input_reg(&mut self) {
-        // filter to bit 4 & 5 as they are the only valid input bits
-        self.input_reg &= 0x30;
+    //The joypad interrupt is requested when a line goes from high to low
+    fn line_changes(&mut self, before: u8) {
+        if before & !self.lines() != 0 {
+            self.interrupt |= 0x10;
+        }
+    }
 
+    //Low nibble of P1, a pressed key pulls its line low when its row is selected.
+    //With both rows selected a line is low when either key is pressed
+    fn lines(&self) -> u8 {
+        let mut lines = 0x0F;
         if self.input_reg & 0x10 == 0 {
-            // bit 4 is low, check R L U D keys
-            let row_res: u8 = self
-                .col_1_keys()
-                .iter()
-                .filter_map(|key| if key.is_down { None } else { Some(key.key_type.value()) })
-                .fold(0, |acc, key_value| acc | key_value);
-
-            self.input_reg |= row_res;
+            lines &= !Self::pressed(self.col_1_keys());
         }
-
         if self.input_reg & 0x20 == 0 {
-            // bit 5 is low, check A B Se St keys
-            let row_res: u8 = self
-                .col_0_keys()
-                .iter()
-                .filter_map(|key| if key.is_down { None } else { Some(key.key_type.value()) })
-                .fold(0, |acc, key_value| acc | key_value);
+            lines &= !Self::pressed(self.col_0_keys());
+        }
+        lines
+    }
+
+    fn pressed(keys: [&Key; 4]) -> u8 {
+        keys.iter().filter(|key| key.is_down).fold(0, |lines, key| lines | key.key_type.value())
+    }
 
-            self.input_reg |= row_res;
+    //True when a pressed key is on a selected row, which pulls its P1 line low
+    pub fn any_line_low(&self) -> bool {
+        self.lines() != 0x0F
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        for key in self.col_0_keys().iter().chain(self.col_1_keys().iter()) {
+            state.bool(key.is_down);
+        }
+        state.u8(self.input_reg);
+        state.u8(self.interrupt);
+        state.u8(self.held.to_byte());
+        state.u8(self.turbo.to_byte());
+        state.u8(self.turbo_counter);
+    }
 
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        for key in [&mut self.a, &mut self.b, &mut self.select, &mut self.start, &mut self.right, &mut self.left, &mut self.up, &mut self.down] {
+            key.is_down = state.bool()?;
         }
+        self.input_reg = state.u8()? & 0x30;
+        self.interrupt = state.u8()?;
+        self.held = Buttons::from_byte(state.u8()?);
+        self.turbo = Buttons::from_byte(state.u8()?);
+        self.turbo_counter = state.u8()?;
+        Ok(())
     }
 
     fn col_0_keys(&self) -> [&Key; 4] {
@@ -138,7 +253,7 @@ impl Input {
 
 impl KeyType {
     pub fn value(&self) -> u8 {
-        // Input values have been incorrectly reordered, this shouldn't work, but it does
+        //Line of the key in the low nibble of P1, one row for the D-pad and one for the buttons
         match *self {
             KeyType::Right | KeyType::A => 0x01,
             KeyType::Left | KeyType::B => 0x02,
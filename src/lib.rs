@@ -0,0 +1,203 @@
+//Game Boy (DMG) emulator core, the window and the sound output are left to the front end
+
+mod processor;
+mod mmu;
+mod ppu;
+mod apu;
+mod input;
+mod serial;
+mod link;
+mod pair;
+#[cfg(feature = "image")]
+mod printer;
+mod cartridge;
+mod gbs;
+mod state;
+mod rewind;
+mod movie;
+#[cfg(feature = "image")]
+mod capture;
+
+use crate::cartridge::MemoryBankController;
+use crate::cartridge::rom::Rom;
+use crate::processor::cpu::Cpu;
+use crate::state::{StateReader, StateWriter};
+
+pub use crate::apu::sound::SAMPLE_RATE;
+pub use crate::cartridge::LoadError;
+#[cfg(feature = "image")]
+pub use crate::capture::{save_screenshot, VideoFormat, VideoRecorder};
+pub use crate::gbs::{GbsHeader, GbsPlayer};
+pub use crate::link::TcpLink;
+pub use crate::pair::LinkedPair;
+#[cfg(feature = "image")]
+pub use crate::printer::Printer;
+pub use crate::input::{Buttons, Key, KeyType, DEFAULT_TURBO_RATE, MAX_TURBO_RATE};
+pub use crate::movie::{Movie, MovieError};
+pub use crate::processor::cpu::Diagnostic;
+pub use crate::serial::{SerialDevice, StdoutSink};
+pub use crate::rewind::Rewind;
+pub use crate::state::{StateError, STATE_VERSION};
+
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+//70224 dots, 154 lines of 456 dots
+pub const CYCLES_PER_FRAME: u32 = 17_556;
+
+pub struct GameBoy {
+    pub(crate) cpu: Cpu,
+    //CRC32 of the rom, written in the save states
+    rom_crc: u32,
+}
+
+impl GameBoy {
+    //Nothing is inserted, the cartridge reads 0xFF like an empty slot
+    pub fn new() -> Self {
+        Self::with_cartridge(Box::new(Rom::new(vec![0xFF; 0x8000])))
+    }
+
+    pub(crate) fn with_cartridge(cartridge: Box<dyn MemoryBankController>) -> Self {
+        Self {
+            rom_crc: state::crc32(cartridge.rom()),
+            cpu: Cpu::with_cartridge(cartridge),
+        }
+    }
+
+    //Insert the rom and power on, battery RAM is not saved. Nothing changes if it is refused
+    pub fn load_rom(&mut self, bytes: Vec<u8>) -> Result<(), LoadError> {
+        *self = Self::with_cartridge(cartridge::from_bytes(bytes, None)?);
+        Ok(())
+    }
+
+    //Same as load_rom but the battery RAM is kept next to the file (.gbsave)
+    pub fn load_rom_file(&mut self, path: &str) -> Result<(), LoadError> {
+        *self = Self::with_cartridge(cartridge::new(path)?);
+        Ok(())
+    }
+
+    //Power cycle, the cartridge and its RAM stay in place
+    pub fn reset(&mut self) {
+        let cartridge = std::mem::replace(&mut self.cpu.bus.rom, Box::new(Rom::new(vec![])));
+        let (allow_opposite, turbo_rate) = (self.cpu.bus.input.allow_opposite, self.cpu.bus.input.turbo_rate);
+        let device = self.cpu.bus.serial.device.take();
+        let capturing = self.cpu.bus.serial.capturing;
+        self.cpu = Cpu::with_cartridge(cartridge);
+        self.cpu.bus.input.allow_opposite = allow_opposite;
+        self.cpu.bus.input.turbo_rate = turbo_rate;
+        self.cpu.bus.serial.device = device;
+        self.cpu.bus.serial.capturing = capturing;
+    }
+
+    //Return the number of M-cycles spent
+    pub fn step_instruction(&mut self) -> u8 {
+        self.cpu.run()
+    }
+
+    //Run until the next VBlank, or a frame worth of cycles when the LCD is off
+    pub fn run_frame(&mut self) -> u32 {
+        self.run_frame_until(|_| false)
+    }
+
+    //Same as run_frame but also stops after the first instruction for which done returns true
+    pub fn run_frame_until<F: FnMut(&Self) -> bool>(&mut self, mut done: F) -> u32 {
+        let mut cycles = 0;
+        self.cpu.bus.gpu.frame_ready = false;
+        while !self.cpu.bus.gpu.frame_ready && cycles < CYCLES_PER_FRAME {
+            cycles += u32::from(self.cpu.run());
+            if done(self) {
+                break;
+            }
+        }
+        self.cpu.bus.gpu.frame_ready = false;
+        self.cpu.bus.input.end_frame();
+        cycles
+    }
+
+    //Title in the cartridge header, empty without a cartridge
+    pub fn title(&self) -> String {
+        let header = self.cpu.bus.rom.rom().get(0x134..0x144).unwrap_or(&[]);
+        //Newer cartridges use the end of the field for other things
+        let title: String = header.iter().take_while(|c| c.is_ascii_graphic() || **c == b' ').map(|&c| char::from(c)).collect();
+        title.trim().to_string()
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.cpu.pc
+    }
+
+    //0xRRGGBB pixels, line by line
+    pub fn framebuffer(&self) -> &[u32; SCREEN_WIDTH * SCREEN_HEIGHT] {
+        &self.cpu.bus.gpu.screen_buffer
+    }
+
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        self.cpu.bus.input.set_buttons(buttons);
+    }
+
+    //Held turbo buttons are pressed for turbo_rate frames then released as long, only
+    //run_frame makes them toggle
+    pub fn set_turbo(&mut self, turbo: Buttons) {
+        self.cpu.bus.input.set_turbo(turbo);
+    }
+
+    //From 1 to MAX_TURBO_RATE frames
+    pub fn set_turbo_rate(&mut self, frames: u8) {
+        self.cpu.bus.input.turbo_rate = frames.clamp(1, MAX_TURBO_RATE);
+    }
+
+    //Left with Right or Up with Down, impossible on a real D-pad, are released by default
+    pub fn allow_opposite_directions(&mut self, allow: bool) {
+        self.cpu.bus.input.allow_opposite = allow;
+    }
+
+    //Interleaved stereo samples at SAMPLE_RATE made since the last call
+    pub fn audio_samples(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.cpu.bus.apu.samples)
+    }
+
+    //Every byte sent through the serial port since power on, while capture_serial is on
+    pub fn serial_output(&self) -> &[u8] {
+        &self.cpu.bus.serial.output
+    }
+
+    //Off by default, the bytes would pile up as long as a game uses the link port.
+    //Turning it off also drops what was kept
+    pub fn capture_serial(&mut self, capture: bool) {
+        self.cpu.bus.serial.capturing = capture;
+        if !capture {
+            self.cpu.bus.serial.output = Vec::new();
+        }
+    }
+
+    //Plug something in the link port, None unplugs it
+    pub fn connect_serial(&mut self, device: Option<Box<dyn SerialDevice>>) {
+        self.cpu.bus.serial.device = device;
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = StateWriter::new(self.rom_crc);
+        self.cpu.save_state(&mut state);
+        state.finish()
+    }
+
+    //On error the machine is left as it was
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let backup = self.save_state();
+        let result = StateReader::new(data, self.rom_crc).and_then(|mut state| self.cpu.load_state(&mut state));
+        if result.is_err() {
+            let mut state = StateReader::new(&backup, self.rom_crc).expect("valid backup state");
+            self.cpu.load_state(&mut state).expect("valid backup state");
+        }
+        result
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.cpu.diagnostics)
+    }
+}
+
+impl Default for GameBoy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,160 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::serial::SerialDevice;
+
+//Longest wait for the other Game Boy, then the byte is lost as if nothing was plugged.
+//Older messages are ignored, the side that sent them has given up
+const TIMEOUT: Duration = Duration::from_millis(100);
+
+//Messages of 3 bytes: kind, sequence number, data
+//The side with the internal clock sends its byte
+const TRANSFER: u8 = 0;
+//The side waiting for the external clock answers with its own
+const REPLY: u8 = 1;
+//Only between the threads, a transfer the reading thread has already replied to
+const ANSWERED: u8 = 2;
+
+//Link cable to another emulator over TCP. Each side runs at its own pace and the side
+//starting a transfer waits for the byte of the other one. When the game of the other one
+//already waits for the external clock its byte is latched and the thread reading the
+//socket answers right away, otherwise the answer comes once its game is ready
+pub struct TcpLink {
+    shared: Arc<Mutex<Shared>>,
+    //Filled by a thread reading the socket, with the time each message came
+    messages: Receiver<([u8; 3], Instant)>,
+    sequence: u8,
+    //Last time the game stopped waiting for the external clock, the transfers answered
+    //before are not for it
+    cancelled: Option<Instant>,
+}
+
+//Written by both threads
+struct Shared {
+    //None once the other side is gone
+    stream: Option<TcpStream>,
+    //Byte the game waits to send with the external clock
+    latched: Option<u8>,
+}
+
+impl Shared {
+    fn send(&mut self, message: [u8; 3]) {
+        if let Some(stream) = self.stream.as_mut() {
+            if stream.write_all(&message).is_err() {
+                self.disconnect();
+            }
+        }
+    }
+
+    fn disconnect(&mut self) {
+        if self.stream.take().is_some() {
+            println!("Link cable disconnected");
+        }
+    }
+}
+
+impl TcpLink {
+    //Wait for the other emulator to connect
+    pub fn listen<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let (stream, _) = TcpListener::bind(address)?.accept()?;
+        Self::new(stream)
+    }
+
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        Self::new(TcpStream::connect(address)?)
+    }
+
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        //Every message is waited for, don't let them sit in a buffer
+        stream.set_nodelay(true)?;
+        let mut reader = stream.try_clone()?;
+        let shared = Arc::new(Mutex::new(Shared { stream: Some(stream), latched: None }));
+        let (sender, messages) = mpsc::channel();
+        let thread_shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            let mut message = [0; 3];
+            while reader.read_exact(&mut message).is_ok() {
+                let mut time = Instant::now();
+                if message[0] == TRANSFER {
+                    let mut shared = thread_shared.lock().unwrap();
+                    if let Some(sent) = shared.latched.take() {
+                        shared.send([REPLY, message[1], sent]);
+                        message[0] = ANSWERED;
+                        //Taken with the lock held to be ordered with cancel
+                        time = Instant::now();
+                    }
+                }
+                if sender.send((message, time)).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self { shared, messages, sequence: 0, cancelled: None })
+    }
+
+    fn send(&mut self, message: [u8; 3]) {
+        self.shared.lock().unwrap().send(message);
+    }
+
+    fn disconnect(&mut self) {
+        self.shared.lock().unwrap().disconnect();
+    }
+}
+
+impl SerialDevice for TcpLink {
+    fn exchange(&mut self, sent: u8) -> u8 {
+        if self.shared.lock().unwrap().stream.is_none() {
+            return 0xFF;
+        }
+        self.sequence = self.sequence.wrapping_add(1);
+        self.send([TRANSFER, self.sequence, sent]);
+
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            match self.messages.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(([REPLY, sequence, received], _)) if sequence == self.sequence => return received,
+                //Both sides started with the internal clock, each gets the byte of the other
+                Ok(([TRANSFER, _, received], time)) if time.elapsed() < TIMEOUT => return received,
+                //Answers to transfers that already gave up
+                Ok(_) => (),
+                Err(RecvTimeoutError::Timeout) => return 0xFF,
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.disconnect();
+                    return 0xFF;
+                }
+            }
+        }
+    }
+
+    fn external_clock(&mut self, sent: u8) -> Option<u8> {
+        loop {
+            match self.messages.try_recv() {
+                //The other side already has its byte, however long ago it came, unless the game
+                //stopped waiting since
+                Ok(([ANSWERED, _, received], time)) if self.cancelled.is_none_or(|cancelled| time > cancelled) => return Some(received),
+                Ok(([TRANSFER, sequence, received], time)) if time.elapsed() < TIMEOUT => {
+                    self.send([REPLY, sequence, sent]);
+                    return Some(received);
+                }
+                Ok(_) => (),
+                Err(TryRecvError::Empty) => {
+                    self.shared.lock().unwrap().latched = Some(sent);
+                    return None;
+                }
+                Err(TryRecvError::Disconnected) => {
+                    self.disconnect();
+                    return None;
+                }
+            }
+        }
+    }
+
+    fn cancel(&mut self) {
+        self.shared.lock().unwrap().latched = None;
+        self.cancelled = Some(Instant::now());
+    }
+}
@@ -1,150 +1,517 @@
+use std::collections::HashMap;
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::{thread, borrow::Cow};
-use std::sync::mpsc::{self, Sender};
+use std::thread::JoinHandle;
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::mpsc::TryRecvError;
-use std::time::SystemTime;
+use std::time::{Duration, Instant};
 
+use config::{Action, Bindings};
+use emul::{Buttons, Diagnostic, GameBoy, GbsPlayer, Key, LinkedPair, Movie, Rewind, SerialDevice, StdoutSink, TcpLink};
+use framebuffer::FrameWriter;
 use glium::{Texture2d, texture, Surface};
-use input::Key;
+use winit::event_loop::EventLoopProxy;
 
-mod processor;
-mod mmu;
-mod ppu;
-mod input;
-mod cartridge;
+mod audio;
+mod config;
+mod display;
+mod framebuffer;
+#[cfg(feature = "gamepad")]
+mod gamepad;
 
 
 extern crate glium;
-use crate::input::KeyType;
 
-fn main() {
+//What the emulation thread runs, a cartridge or a music rip
+enum Machine {
+    Cartridge(GameBoy),
+    Music(GbsPlayer),
+    //Side by side and linked, the controls go to one of them at a time
+    Pair(Box<LinkedPair>),
+}
 
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        panic!("No path for a GB rom after 'cargo run', {}",args.len());
+//A movie recorded or played by the emulation thread
+enum MovieMode {
+    //Written when the emulation stops
+    Record { movie: Movie, path: PathBuf },
+    Play { movie: Movie, frame: usize },
+}
+
+impl MovieMode {
+    //Held and turbo buttons of the frame about to run, the recording keeps the ones given and
+    //the playback replaces them. None once the playback is over
+    fn next_frame(&mut self, held: Buttons, turbo: Buttons) -> Option<(Buttons, Buttons)> {
+        match self {
+            MovieMode::Record { movie, .. } => {
+                movie.push(held, turbo);
+                Some((held, turbo))
+            }
+            MovieMode::Play { movie, frame } => {
+                *frame += 1;
+                movie.frame(*frame - 1)
+            }
+        }
     }
+}
 
+//Rewind defaults, a state every frame in at most 64 MB
+const REWIND_INTERVAL: u32 = 1;
+const REWIND_BUDGET_MB: usize = 64;
 
-    let (screen_sender, screen_receiver) = mpsc::channel();
-    let (key_sender, key_receiver) = mpsc::channel();
-    let (stop_sender, stop_receiver) = mpsc::channel();
+//Speeds chosen with + and -, below 1 is slow motion
+const SPEEDS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+const NORMAL_SPEED: usize = 2;
 
-    let mut cpu = processor::cpu::Cpu::new(&args[1], screen_sender, key_receiver, stop_receiver);
+//The CPU runs 1 048 576 M-cycles per second, a frame is 17 556 of them (70 224 dots)
+const CYCLES_PER_SECOND: f64 = 1_048_576.0;
+//Further behind than this the lost time is given up instead of running frames back to back
+const MAX_LATENESS: Duration = Duration::from_millis(100);
+//How often the FPS and speed are measured
+const STATS_PERIOD: Duration = Duration::from_secs(1);
+//Gamepads don't wake the window up, they are polled at least this often
+#[cfg(feature = "gamepad")]
+const GAMEPAD_POLL: Duration = Duration::from_millis(8);
 
-    thread::spawn(move || {
-        let mut now = SystemTime::now();
-        loop {
-            // each cycle take around 238 ns because in 1s 4 194 304 cycle are made not most accurate but my pov on it
-            let timed_cycle=cpu.run()as u128*238*4;
-            let mut difference=SystemTime::now().duration_since(now).expect("Le temps actuel est antérieur au temps de départ.").as_nanos();
+#[derive(Clone, Copy, PartialEq)]
+enum Speed {
+    //Multiple of the real speed
+    Factor(f64),
+    Uncapped,
+}
 
-            match cpu.stop.try_recv() {
-                Ok(_data) => break,
-                Err(TryRecvError::Empty) => (),
-                _ => {}
-            }
+//Messages from the window to the emulation thread
+enum Control {
+    Stop,
+    Pause(bool),
+    //Run a single frame while paused
+    FrameAdvance,
+    Speed(Speed),
+    //Save state slots, from F1 to F4
+    SaveState(u8),
+    LoadState(u8),
+    //True while the rewind key is held
+    Rewind(bool),
+    //A turbo button pressed or released
+    Turbo(Key),
+    //The controls go to the other Game Boy of the pair
+    SwitchPlayer,
+    Screenshot,
+    //Start or stop the video
+    RecordVideo,
+}
+
+//Measured by the emulation thread, shown in the title
+#[derive(Clone, Copy)]
+struct Stats {
+    fps: f64,
+    //Percentage of the real Game Boy speed
+    speed: f64,
+}
+
+//What the window shows in its title
+struct Status {
+    gbs_track: Option<(u8, u8)>,
+    paused: bool,
+    speed_index: usize,
+    //Tab held, as fast as possible
+    fast_forward: bool,
+    locked: bool,
+    stats: Option<Stats>,
+    //Index of the Game Boy of the pair the controls go to
+    player: Option<usize>,
+}
+
+impl Status {
+    fn speed(&self) -> Speed {
+        if self.fast_forward {
+            Speed::Uncapped
+        } else {
+            Speed::Factor(SPEEDS[self.speed_index])
+        }
+    }
 
-            //wait until the cpu catch our
-            while difference<timed_cycle{
-                difference= SystemTime::now().duration_since(now).expect("Le temps actuel est antérieur au temps de départ.").as_nanos();
+    fn title(&self, title: &str) -> String {
+        let mut text = match self.gbs_track {
+            Some((track, song_count)) => format!("{} [{}/{}]", title, track + 1, song_count),
+            None => title.to_string(),
+        };
+        if let Some(player) = self.player {
+            text.push_str(&format!(" [Player {}]", player + 1));
+        }
+        if self.paused {
+            text.push_str(" [Paused]");
+        } else {
+            match self.speed() {
+                Speed::Uncapped => text.push_str(" [Fast forward]"),
+                Speed::Factor(factor) if self.speed_index != NORMAL_SPEED => text.push_str(&format!(" [x{}]", factor)),
+                Speed::Factor(_) => (),
+            }
+            if let Some(stats) = self.stats {
+                text.push_str(&format!(" [{:.1} FPS, {:.0}%]", stats.fps, stats.speed));
             }
+        }
+        if self.locked {
+            text.push_str(" (CPU locked)");
+        }
+        text
+    }
+}
+
+//Window side of the channels with the emulation
+struct Senders {
+    control: Sender<Control>,
+    key: Sender<Key>,
+    track: Sender<u8>,
+    //Keys, gamepad buttons and sticks holding each held action, it is released by the last one
+    holders: HashMap<Action, u32>,
+}
+
+//Where the screenshots and the videos go, only used with the image feature
+#[cfg_attr(not(feature = "image"), allow(dead_code))]
+struct Capture {
+    directory: PathBuf,
+    //Each pixel of the screenshots is saved as a square this wide
+    scale: u32,
+    #[cfg(feature = "image")]
+    video_format: emul::VideoFormat,
+}
 
-            now = SystemTime::now();
+//Emulation side of the channels with the window
+struct Channels {
+    screen: FrameWriter,
+    audio: Sender<Vec<f32>>,
+    diagnostic: Sender<Diagnostic>,
+    stats: Sender<Stats>,
+    key: Receiver<Key>,
+    track: Receiver<u8>,
+    control: Receiver<Control>,
+    //With vsync, one message each time the window has shown a frame
+    presented: Option<Receiver<()>>,
+    //Wakes the window up when a frame is published, it sleeps otherwise
+    wake: EventLoopProxy<()>,
+}
+
+//Also when the thread panics, the window then sees the screen closed and exits
+impl Drop for Channels {
+    fn drop(&mut self) {
+        let _ = self.wake.send_event(());
+    }
+}
+
+fn main() {
+
+    let args: Vec<String> = env::args().collect();
+    let mut rom_path = None;
+    let mut rewind_interval = REWIND_INTERVAL;
+    let mut rewind_budget = REWIND_BUDGET_MB;
+    let mut vsync = false;
+    let mut allow_opposite = false;
+    let mut turbo_rate = emul::DEFAULT_TURBO_RATE;
+    let mut serial_stdout = false;
+    let mut link_listen = None;
+    let mut link_connect = None;
+    let mut printer = None;
+    let mut config_path = config::default_path();
+    let mut print_keys = false;
+    let mut record_movie = None;
+    let mut record_from_slot = None;
+    let mut play_movie = None;
+    let mut second_rom = None;
+    let mut screenshot_dir = None;
+    let mut screenshot_scale = 1;
+    let mut record_video = false;
+    let mut video_format = String::from("gif");
+    let mut options = args.iter().skip(1);
+    while let Some(arg) = options.next() {
+        match arg.as_str() {
+            "--rewind-interval" => rewind_interval = options.next().and_then(|value| value.parse().ok()).expect("--rewind-interval needs a number of frames"),
+            "--rewind-budget" => rewind_budget = options.next().and_then(|value| value.parse().ok()).expect("--rewind-budget needs a size in MB"),
+            "--vsync" => vsync = true,
+            "--allow-opposite-directions" => allow_opposite = true,
+            "--serial-stdout" => serial_stdout = true,
+            "--link-listen" => link_listen = Some(options.next().expect("--link-listen needs an address like 0.0.0.0:8765").clone()),
+            "--link-connect" => link_connect = Some(options.next().expect("--link-connect needs an address like 127.0.0.1:8765").clone()),
+            "--printer" => printer = Some(options.next().expect("--printer needs a directory for the printed images").clone()),
+            "--turbo-rate" => turbo_rate = options.next().and_then(|value| value.parse().ok()).filter(|rate| (1..=emul::MAX_TURBO_RATE).contains(rate)).expect("--turbo-rate needs a number of frames from 1 to 127"),
+            "--config" => config_path = Some(PathBuf::from(options.next().expect("--config needs a path"))),
+            "--print-keys" => print_keys = true,
+            "--record-movie" => record_movie = Some(PathBuf::from(options.next().expect("--record-movie needs a path"))),
+            "--record-from-slot" => record_from_slot = Some(options.next().and_then(|value| value.parse().ok()).expect("--record-from-slot needs a state slot")),
+            "--play-movie" => play_movie = Some(PathBuf::from(options.next().expect("--play-movie needs a path"))),
+            "--screenshot-dir" => screenshot_dir = Some(PathBuf::from(options.next().expect("--screenshot-dir needs a directory"))),
+            "--screenshot-scale" => screenshot_scale = options.next().and_then(|value| value.parse().ok()).filter(|&scale| scale > 0).expect("--screenshot-scale needs a scale from 1"),
+            "--record-video" => record_video = true,
+            "--video-format" => video_format = options.next().expect("--video-format needs gif or png").clone(),
+            "--second-rom" => second_rom = Some(options.next().expect("--second-rom needs the path of a GB rom").clone()),
+            _ => rom_path = Some(arg.clone()),
         }
-    });
+    }
+    let bindings = config_path.as_deref().map_or_else(Bindings::default, Bindings::load);
+    if print_keys {
+        if let Some(path) = &config_path {
+            println!("# {}", path.display());
+        }
+        print!("{}", bindings.describe());
+        return;
+    }
+    let Some(rom_path) = rom_path else {
+        panic!("No path for a GB rom after 'cargo run'");
+    };
+    if video_format != "gif" && video_format != "png" {
+        panic!("Unknown video format '{}', gif or png", video_format);
+    }
+    //The two Game Boys use the link port and have no state the movies could start from
+    if second_rom.is_some() && (link_listen.is_some() || link_connect.is_some() || printer.is_some() || serial_stdout || record_movie.is_some() || play_movie.is_some()) {
+        panic!("--second-rom can't be used with the link cable, the printer, --serial-stdout or the movies");
+    }
+
+
+    let screens = if second_rom.is_some() { 2 } else { 1 };
+    let (screen_writer, mut screen_reader) = framebuffer::triple_buffer(screens);
+    let (audio_sender, audio_receiver) = mpsc::channel();
+    let (key_sender, key_receiver) = mpsc::channel();
+    let (control_sender, control_receiver) = mpsc::channel();
+    let (track_sender, track_receiver) = mpsc::channel();
+    let (diagnostic_sender, diagnostic_receiver) = mpsc::channel();
+    let (stats_sender, stats_receiver) = mpsc::channel();
+    let (presented_sender, presented_receiver) = mpsc::channel();
+
+    let mut title = String::from("Wow une image");
+    let mut status = Status {
+        //Music player mode, keys change the track instead of going to the joypad
+        gbs_track: None,
+        paused: false,
+        speed_index: NORMAL_SPEED,
+        fast_forward: false,
+        locked: false,
+        stats: None,
+        player: second_rom.is_some().then_some(0),
+    };
+    //Shift + F1..F4 loads the slot instead of saving it
+    let mut modifiers = winit::event::ModifiersState::empty();
+    //winit repeats the presses of a held key, only the first one acts. The actions are the
+    //ones of the press, Shift may have changed when the key is released
+    let mut keys_down: HashMap<winit::event::VirtualKeyCode, Vec<Action>> = HashMap::new();
+
+    let mut movie = None;
+    let machine = if Path::new(&rom_path).extension().and_then(|ext| ext.to_str()) == Some("gbs") {
+        let bytes = fs::read(&rom_path).unwrap_or_else(|err| panic!("Error opening the file: {}", err));
+        let player = GbsPlayer::new(bytes).unwrap_or_else(|err| panic!("Error loading the music: {}", err));
+        title = format!("{} - {}", player.header.title, player.header.author);
+        status.gbs_track = Some((player.header.first_song.saturating_sub(1) % player.header.song_count, player.header.song_count));
+        println!("{} by {} ({}), {} songs", player.header.title, player.header.author, player.header.copyright, player.header.song_count);
+        Machine::Music(player)
+    } else if let Some(second_rom) = &second_rom {
+        //Only the first one keeps its battery RAM, the same rom may be given twice
+        let bytes = fs::read(second_rom).unwrap_or_else(|err| panic!("Error opening the second rom: {}", err));
+        let mut gameboys = [GameBoy::new(), GameBoy::new()];
+        gameboys[0].load_rom_file(&rom_path).unwrap_or_else(|err| panic!("Error loading the rom: {}", err));
+        gameboys[1].load_rom(bytes).unwrap_or_else(|err| panic!("Error loading the second rom: {}", err));
+        for gameboy in &mut gameboys {
+            gameboy.allow_opposite_directions(allow_opposite);
+            gameboy.set_turbo_rate(turbo_rate);
+        }
+        let [first, second] = gameboys;
+        Machine::Pair(Box::new(LinkedPair::new(first, second)))
+    } else {
+        let mut gameboy = GameBoy::new();
+        gameboy.load_rom_file(&rom_path).unwrap_or_else(|err| panic!("Error loading the rom: {}", err));
+        gameboy.allow_opposite_directions(allow_opposite);
+        gameboy.set_turbo_rate(turbo_rate);
+        //The link cable takes the port before the printer, printing the bytes needs it free
+        if let Some(address) = &link_listen {
+            println!("Waiting for the other emulator on {}", address);
+            let link = TcpLink::listen(address).unwrap_or_else(|err| panic!("Error waiting for the link cable: {}", err));
+            gameboy.connect_serial(Some(Box::new(link)));
+        } else if let Some(address) = &link_connect {
+            let link = TcpLink::connect(address).unwrap_or_else(|err| panic!("Error connecting the link cable: {}", err));
+            gameboy.connect_serial(Some(Box::new(link)));
+        } else if let Some(directory) = &printer {
+            gameboy.connect_serial(Some(connect_printer(directory)));
+        } else if serial_stdout {
+            gameboy.connect_serial(Some(Box::new(StdoutSink)));
+        }
+        if let Some(path) = record_movie {
+            //Without a slot the recording starts from a power cycle
+            if let Some(slot) = record_from_slot {
+                load_state(&mut gameboy, Path::new(&rom_path), slot);
+            }
+            movie = Some(MovieMode::Record { movie: Movie::record(&mut gameboy, record_from_slot.is_none()), path });
+        } else if let Some(path) = play_movie {
+            let data = fs::read(&path).unwrap_or_else(|err| panic!("Error opening the movie: {}", err));
+            let played = Movie::from_bytes(&data).unwrap_or_else(|err| panic!("Error reading the movie: {}", err));
+            played.start(&mut gameboy).unwrap_or_else(|err| panic!("Error playing the movie: {}", err));
+            println!("Playing {} frames", played.frame_count());
+            movie = Some(MovieMode::Play { movie: played, frame: 0 });
+        }
+        Machine::Cartridge(gameboy)
+    };
 
+    let rewind = Rewind::new(rewind_interval, rewind_budget * 1024 * 1024);
+    //Next to the rom by default, like the states
+    let capture = Capture {
+        directory: screenshot_dir.unwrap_or_else(|| Path::new(&rom_path).parent().map(Path::to_path_buf).unwrap_or_default()),
+        scale: screenshot_scale,
+        #[cfg(feature = "image")]
+        video_format: if video_format == "png" { emul::VideoFormat::Png } else { emul::VideoFormat::Gif },
+    };
     //Screen setup for glium last version and the winit version associated
     let event_loop = winit::event_loop::EventLoopBuilder::new()
         .build();
 
-    let (_window, display) = glium::backend::glutin::SimpleWindowBuilder::new()
-        .with_inner_size(160*3,144*3)
-        .with_title("Wow une image")
-        .build(&event_loop);
+    let mut emulation = Some(spawn_emulation(machine, PathBuf::from(&rom_path), rewind, movie, capture, Channels {
+        screen: screen_writer,
+        audio: audio_sender,
+        diagnostic: diagnostic_sender,
+        stats: stats_sender,
+        key: key_receiver,
+        track: track_receiver,
+        control: control_receiver,
+        presented: vsync.then_some(presented_receiver),
+        wake: event_loop.create_proxy(),
+    }));
+
+    let mut senders = Senders {
+        control: control_sender,
+        key: key_sender,
+        track: track_sender,
+        holders: HashMap::new(),
+    };
+    //Before the first frame
+    if record_video {
+        let _ = senders.control.send(Control::RecordVideo);
+    }
+
+    #[cfg(feature = "gamepad")]
+    let mut gamepads = gamepad::Gamepads::new();
+
+    //Keep the stream alive as long as the window
+    let _audio_output = audio::start(audio_receiver);
+
+    let (window, display) = display::build(&event_loop, &status.title(&title), 160*3*screens as u32, 144*3, vsync);
 
     let texture = Texture2d::empty_with_format(
         &display,
-        //no format u32 so translate u32 to u8u8u8 later
         texture::UncompressedFloatFormat::U8U8U8U8,
         texture::MipmapsOption::NoMipmap,
-        160,
+        160*screens as u32,
         144,
     ).unwrap();
 
 
     event_loop.run(move |event, _, control_flow| {
-        *control_flow = winit::event_loop::ControlFlow::Poll;
-        match screen_receiver.try_recv() {
-            Ok(data) => {
-                //glium doesn't like u32 texture so translate to u8u8u8
-                let mut dataa= vec![0u8; (160 * 144 * 3) as usize];
-                for i in 0..data.len() {
-                    dataa[i * 3] = (data[i] & 0xFF) as u8;
-                    dataa[i * 3 + 1] = ((data[i] >> 8) & 0xFF) as u8;
-                    dataa[i * 3 + 2] = ((data[i] >> 16) & 0xFF) as u8;
+        //Until the next frame or input
+        *control_flow = winit::event_loop::ControlFlow::Wait;
+        #[cfg(feature = "gamepad")]
+        if gamepads.is_some() {
+            control_flow.set_wait_until(Instant::now() + GAMEPAD_POLL);
+        }
+        while let Ok(diagnostic) = diagnostic_receiver.try_recv() {
+            match diagnostic {
+                Diagnostic::Locked { opcode, pc } => {
+                    println!("CPU locked by illegal opcode 0x{:02X} at 0x{:04X}", opcode, pc);
+                    status.locked = true;
+                    window.set_title(&status.title(&title));
+                }
+            }
+        }
+        #[cfg(feature = "gamepad")]
+        if let Some(gamepads) = gamepads.as_mut() {
+            for (action, is_down) in gamepads.poll(&bindings) {
+                if senders.apply(action, is_down, &mut status) {
+                    window.set_title(&status.title(&title));
                 }
+            }
+        }
+        if let Some(stats) = stats_receiver.try_iter().last() {
+            status.stats = Some(stats);
+            window.set_title(&status.title(&title));
+        }
+        if let Some(frame) = screen_reader.latest() {
+            let image = texture::RawImage2d {
+                data: Cow::Borrowed(frame),
+                width: 160*screens as u32,
+                height: 144,
+                format: texture::ClientFormat::U8U8U8U8,
+            };
 
-                let image = texture::RawImage2d {
-                    data: Cow::Borrowed(&dataa),
-                    width: 160,
+            // Mettre à jour la texture avec les nouvelles données
+            texture.write(
+                glium::Rect {
+                    left: 0,
+                    bottom: 0,
+                    width: 160*screens as u32,
                     height: 144,
-                    format: texture::ClientFormat::U8U8U8,
-                };
+                },
+                image,
+            );
 
-                // Mettre à jour la texture avec les nouvelles données
-                texture.write(
-                    glium::Rect {
-                        left: 0,
-                        bottom: 0,
-                        width: 160,
-                        height: 144,
-                    },
-                    image,
-                );
-
-                let target = display.draw();
-                let (unsigned_width, unsigned_height) = target.get_dimensions();
-
-                let width = i32::from(unsigned_width as u16);
-                let height = i32::from(unsigned_height as u16);
-                let blit_target = glium::BlitTarget {
-                    left: 0,
-                    bottom: height as u32,
-                    width,
-                    height: -height,
-                };
-                texture.as_surface().blit_whole_color_to(
-                    &target,
-                    &blit_target,
-                    glium::uniforms::MagnifySamplerFilter::Nearest,
-                );
-                if let Err(e) = target.finish() {
-                    println!("ERROR: Failed to write to display: {}", e)
-                }
-            },
-            Err(TryRecvError::Empty) => (),
-            Err(TryRecvError::Disconnected) =>{control_flow.set_exit();},
+            let target = display.draw();
+            let (unsigned_width, unsigned_height) = target.get_dimensions();
 
+            let width = i32::from(unsigned_width as u16);
+            let height = i32::from(unsigned_height as u16);
+            let blit_target = glium::BlitTarget {
+                left: 0,
+                bottom: height as u32,
+                width,
+                height: -height,
+            };
+            texture.as_surface().blit_whole_color_to(
+                &target,
+                &blit_target,
+                glium::uniforms::MagnifySamplerFilter::Nearest,
+            );
+            if let Err(e) = target.finish() {
+                println!("ERROR: Failed to write to display: {}", e)
+            }
+            if vsync {
+                let _ = presented_sender.send(());
+            }
+        } else if screen_reader.is_closed() {
+            control_flow.set_exit();
         }
 
         if let winit::event::Event::WindowEvent { event, .. } = event {
            match event {
         
-               winit::event::WindowEvent::CloseRequested => {stop_sender.send(true).unwrap();
+               winit::event::WindowEvent::CloseRequested => {senders.control.send(Control::Stop).unwrap();
+                   //Let it write the movie and the battery ram before the process ends
+                   if let Some(emulation) = emulation.take() {
+                       let _ = emulation.join();
+                   }
                    control_flow.set_exit();},
+               winit::event::WindowEvent::ModifiersChanged(state) => modifiers = state,
+               //The releases happening in another window are not seen, the keys are let go now
+               winit::event::WindowEvent::Focused(false) => {
+                   for action in keys_down.drain().flat_map(|(_, actions)| actions) {
+                       if senders.apply(action, false, &mut status) {
+                           window.set_title(&status.title(&title));
+                       }
+                   }
+               }
                winit::event::WindowEvent::KeyboardInput { input, .. } => {
                    let is_down = input.state == winit::event::ElementState::Pressed;
-        
-                   match input.virtual_keycode {
-                       Some(winit::event::VirtualKeyCode::Up) => send_key_event(&key_sender, KeyType::Up, is_down),
-                       Some(winit::event::VirtualKeyCode::Down) => send_key_event(&key_sender, KeyType::Down, is_down),
-                       Some(winit::event::VirtualKeyCode::Left) => send_key_event(&key_sender, KeyType::Left, is_down),
-                       Some(winit::event::VirtualKeyCode::Right) => send_key_event(&key_sender, KeyType::Right, is_down),
-                       Some(winit::event::VirtualKeyCode::Q) => send_key_event(&key_sender, KeyType::A, is_down),
-                       Some(winit::event::VirtualKeyCode::S) => send_key_event(&key_sender, KeyType::B, is_down),
-                       Some(winit::event::VirtualKeyCode::W) => send_key_event(&key_sender, KeyType::Select, is_down),
-                       Some(winit::event::VirtualKeyCode::X) => send_key_event(&key_sender, KeyType::Start, is_down),
-                       _ => (),
+
+                   let Some(key) = input.virtual_keycode else {
+                       return;
+                   };
+                   let actions = if is_down {
+                       if keys_down.contains_key(&key) {
+                           return;
+                       }
+                       let actions = bindings.actions(key, modifiers.shift());
+                       keys_down.insert(key, actions.clone());
+                       actions
+                   } else {
+                       keys_down.remove(&key).unwrap_or_default()
+                   };
+                   for action in actions {
+                       if senders.apply(action, is_down, &mut status) {
+                           window.set_title(&status.title(&title));
+                       }
                    }
                }
                _ => (),
@@ -153,13 +520,373 @@ fn main() {
     });
 }
 
-fn send_key_event(
-    key_sender: &Sender<Key>,
-    key_type: KeyType,
-    is_down: bool,
-) {
-    let _ = key_sender.send(Key {
-        key_type,
-        is_down,
-    });
-}
\ No newline at end of file
+impl Senders {
+    //Send what the action does to the emulation, true when the title changed
+    fn apply(&mut self, action: Action, is_down: bool, status: &mut Status) -> bool {
+        if matches!(action, Action::Button(_) | Action::Turbo(_) | Action::Rewind | Action::FastForward) {
+            let holders = self.holders.entry(action).or_default();
+            let was_held = *holders > 0;
+            if is_down {
+                *holders += 1;
+            } else {
+                *holders = holders.saturating_sub(1);
+            }
+            if was_held == (*holders > 0) {
+                return false;
+            }
+        }
+        let control = match action {
+            Action::Button(key_type) => {
+                //Music files use the keys for the tracks
+                if status.gbs_track.is_none() {
+                    let _ = self.key.send(Key { key_type, is_down });
+                }
+                return false;
+            }
+            Action::NextTrack | Action::PreviousTrack => {
+                let Some((track, song_count)) = status.gbs_track.as_mut() else {
+                    return false;
+                };
+                if !is_down {
+                    return false;
+                }
+                *track = if action == Action::NextTrack { (*track + 1) % *song_count } else { (*track + *song_count - 1) % *song_count };
+                let _ = self.track.send(*track);
+                return true;
+            }
+            Action::Turbo(key_type) => {
+                if status.gbs_track.is_none() {
+                    let _ = self.control.send(Control::Turbo(Key { key_type, is_down }));
+                }
+                return false;
+            }
+            Action::Rewind => Control::Rewind(is_down),
+            Action::FastForward => {
+                status.fast_forward = is_down;
+                Control::Speed(status.speed())
+            }
+            //The others act when pressed
+            _ if !is_down => return false,
+            Action::Pause => {
+                status.paused = !status.paused;
+                status.stats = None;
+                Control::Pause(status.paused)
+            }
+            Action::FrameAdvance => Control::FrameAdvance,
+            Action::SpeedUp => {
+                status.speed_index = (status.speed_index + 1).min(SPEEDS.len() - 1);
+                Control::Speed(status.speed())
+            }
+            Action::SpeedDown => {
+                status.speed_index = status.speed_index.saturating_sub(1);
+                Control::Speed(status.speed())
+            }
+            Action::SaveState(slot) => Control::SaveState(slot),
+            Action::LoadState(slot) => Control::LoadState(slot),
+            Action::SwitchPlayer => {
+                let Some(player) = status.player.as_mut() else {
+                    return false;
+                };
+                *player = 1 - *player;
+                Control::SwitchPlayer
+            }
+            Action::Screenshot => Control::Screenshot,
+            Action::RecordVideo => Control::RecordVideo,
+        };
+        let _ = self.control.send(control);
+        true
+    }
+}
+
+//The printed images go in the directory, made if needed
+#[cfg(feature = "image")]
+fn connect_printer(directory: &str) -> Box<dyn SerialDevice> {
+    fs::create_dir_all(directory).unwrap_or_else(|err| panic!("Error making the printer directory: {}", err));
+    Box::new(emul::Printer::new(directory))
+}
+
+#[cfg(not(feature = "image"))]
+fn connect_printer(_directory: &str) -> Box<dyn SerialDevice> {
+    panic!("--printer needs the image feature");
+}
+
+//What is on screen, both Game Boys of a pair side by side
+#[cfg(feature = "image")]
+fn screenshot(machine: &mut Machine, capture: &Capture) {
+    let title = machine.title();
+    let gameboys = machine.gameboys();
+    let screens: Vec<&[u32]> = gameboys.iter().map(|gameboy| &gameboy.framebuffer()[..]).collect();
+    match emul::save_screenshot(&screens, &title, &capture.directory, capture.scale) {
+        Ok(path) => println!("Screenshot saved in {}", path.display()),
+        Err(err) => println!("Failed to save the screenshot in {}: {}", capture.directory.display(), err),
+    }
+}
+
+#[cfg(not(feature = "image"))]
+fn screenshot(_machine: &mut Machine, _capture: &Capture) {
+    println!("Screenshots need the image feature");
+}
+
+#[cfg(feature = "image")]
+fn toggle_video(video: &mut Option<emul::VideoRecorder>, machine: &mut Machine, capture: &Capture) {
+    if let Some(recorder) = video.take() {
+        finish_video(recorder);
+        return;
+    }
+    match emul::VideoRecorder::start(capture.video_format, &machine.title(), &capture.directory) {
+        Ok(recorder) => {
+            println!("Recording the video in {}", recorder.path().display());
+            *video = Some(recorder);
+        }
+        Err(err) => println!("Failed to start the video in {}: {}", capture.directory.display(), err),
+    }
+}
+
+#[cfg(feature = "image")]
+fn finish_video(recorder: emul::VideoRecorder) {
+    let path = recorder.path().to_path_buf();
+    match recorder.finish() {
+        Ok(frames) => println!("Video of {} frames written in {}", frames, path.display()),
+        Err(err) => println!("Failed to write the video in {}: {}", path.display(), err),
+    }
+}
+
+//States are kept next to the rom, one file per slot
+fn save_state(gameboy: &GameBoy, rom_path: &Path, slot: u8) {
+    let path = rom_path.with_extension(format!("state{}", slot));
+    match fs::write(&path, gameboy.save_state()) {
+        Ok(()) => println!("State saved in slot {}", slot),
+        Err(err) => println!("Failed to save the state in {}: {}", path.display(), err),
+    }
+}
+
+fn load_state(gameboy: &mut GameBoy, rom_path: &Path, slot: u8) {
+    let path = rom_path.with_extension(format!("state{}", slot));
+    match fs::read(&path).map_err(|err| err.to_string()).and_then(|data| gameboy.load_state(&data).map_err(|err| err.to_string())) {
+        Ok(()) => println!("State loaded from slot {}", slot),
+        Err(err) => println!("Failed to load the state from {}: {}", path.display(), err),
+    }
+}
+
+impl Machine {
+    fn gameboy(&mut self) -> &mut GameBoy {
+        match self {
+            Machine::Cartridge(gameboy) => gameboy,
+            Machine::Music(player) => player.gameboy(),
+            Machine::Pair(pair) => &mut pair.gameboys_mut()[0],
+        }
+    }
+
+    //For the file names
+    #[cfg(feature = "image")]
+    fn title(&mut self) -> String {
+        match self {
+            Machine::Music(player) => player.header.title.clone(),
+            _ => self.gameboy().title(),
+        }
+    }
+
+    //The ones on screen, from left to right
+    fn gameboys(&mut self) -> &mut [GameBoy] {
+        match self {
+            Machine::Pair(pair) => pair.gameboys_mut(),
+            _ => std::slice::from_mut(self.gameboy()),
+        }
+    }
+}
+
+//Run the emulation in its own thread until the stop message
+fn spawn_emulation(mut machine: Machine, rom_path: PathBuf, mut rewind: Rewind, mut movie: Option<MovieMode>, capture: Capture, mut channels: Channels) -> JoinHandle<()> {
+    thread::spawn(move || {
+        #[cfg(feature = "image")]
+        let mut video = None;
+        let mut buttons = Buttons::default();
+        let mut turbo = Buttons::default();
+        let mut rewinding = false;
+        let mut player = 0;
+        let mut paused = false;
+        let mut speed = Speed::Factor(1.0);
+        //When the frames run so far should have ended in real time
+        let mut deadline = Instant::now();
+        let mut stats_start = Instant::now();
+        let mut stats_frames = 0;
+        let mut stats_cycles = 0;
+        'emulation: loop {
+            let mut advance = false;
+            let mut waited = false;
+            loop {
+                //Wait for the next message instead of spinning while paused
+                let control = if paused && !advance {
+                    waited = true;
+                    match channels.control.recv() {
+                        Ok(control) => control,
+                        Err(_) => break 'emulation,
+                    }
+                } else {
+                    match channels.control.try_recv() {
+                        Ok(control) => control,
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => break 'emulation,
+                    }
+                };
+                match control {
+                    Control::Stop => break 'emulation,
+                    Control::Pause(pause) => paused = pause,
+                    Control::FrameAdvance => advance = true,
+                    Control::Speed(new_speed) => speed = new_speed,
+                    //A state of one of them would break the link with the other
+                    Control::SaveState(_) | Control::LoadState(_) if matches!(machine, Machine::Pair(_)) => println!("States are not available with two Game Boys"),
+                    Control::Rewind(true) if matches!(machine, Machine::Pair(_)) => println!("Rewind is not available with two Game Boys"),
+                    Control::SaveState(slot) => save_state(machine.gameboy(), &rom_path, slot),
+                    //The movie would not match the buttons any more
+                    Control::LoadState(_) if movie.is_some() => println!("States can't be loaded while a movie runs"),
+                    Control::LoadState(slot) => load_state(machine.gameboy(), &rom_path, slot),
+                    Control::Rewind(held) => rewinding = held && movie.is_none() && !matches!(machine, Machine::Pair(_)),
+                    Control::Turbo(key) => turbo.set(&key),
+                    Control::SwitchPlayer => {
+                        //What the other one held stays held otherwise
+                        let gameboys = machine.gameboys();
+                        gameboys[player].set_buttons(Buttons::default());
+                        gameboys[player].set_turbo(Buttons::default());
+                        player = (player + 1) % gameboys.len();
+                    }
+                    Control::Screenshot => screenshot(&mut machine, &capture),
+                    #[cfg(feature = "image")]
+                    Control::RecordVideo => toggle_video(&mut video, &mut machine, &capture),
+                    #[cfg(not(feature = "image"))]
+                    Control::RecordVideo => println!("Videos need the image feature"),
+                }
+            }
+            //The time spent paused is neither late nor part of the measure
+            if waited {
+                deadline = Instant::now();
+                stats_start = deadline;
+                stats_frames = 0;
+                stats_cycles = 0;
+            }
+
+            match &mut machine {
+                Machine::Cartridge(gameboy) => {
+                    while let Ok(key) = channels.key.try_recv() {
+                        buttons.set(&key);
+                    }
+                    let (frame_buttons, frame_turbo) = match movie.as_mut().map(|movie| movie.next_frame(buttons, turbo)) {
+                        Some(Some(frame)) => frame,
+                        Some(None) => {
+                            println!("Movie finished, the controls are back");
+                            movie = None;
+                            (buttons, turbo)
+                        }
+                        None => (buttons, turbo),
+                    };
+                    gameboy.set_buttons(frame_buttons);
+                    gameboy.set_turbo(frame_turbo);
+                }
+                Machine::Music(player) => {
+                    if let Ok(track) = channels.track.try_recv() {
+                        player.play(track);
+                    }
+                }
+                Machine::Pair(pair) => {
+                    while let Ok(key) = channels.key.try_recv() {
+                        buttons.set(&key);
+                    }
+                    let gameboy = &mut pair.gameboys_mut()[player];
+                    gameboy.set_buttons(buttons);
+                    gameboy.set_turbo(turbo);
+                }
+            }
+
+            let cycle = if let Machine::Pair(pair) = &mut machine {
+                pair.run_frame()
+            } else {
+                let gameboy = machine.gameboy();
+                //The screen is not in the states, the frame after the one restored draws it
+                if rewinding && rewind.rewind(gameboy) {
+                    gameboy.run_frame()
+                } else {
+                    let cycle = gameboy.run_frame();
+                    rewind.record(gameboy);
+                    cycle
+                }
+            };
+            //Acknowledgements of older frames are not for this one
+            let presented = channels.presented.as_ref().filter(|_| speed == Speed::Factor(1.0));
+            if let Some(presented) = presented {
+                while presented.try_recv().is_ok() {}
+            }
+            let gameboys = machine.gameboys();
+            //The first Game Boy of a pair is the one heard
+            let samples = gameboys[0].audio_samples();
+            let screens: Vec<&[u32]> = gameboys.iter().map(|gameboy| &gameboy.framebuffer()[..]).collect();
+            channels.screen.publish(&screens);
+            let _ = channels.wake.send_event(());
+            //Every frame run, whatever the speed
+            #[cfg(feature = "image")]
+            if let Some(Err(err)) = video.as_mut().map(|recorder| recorder.push(&screens, &samples)) {
+                println!("Video stopped: {}", err);
+                //What was written stays readable
+                if let Some(recorder) = video.take() {
+                    finish_video(recorder);
+                }
+            }
+            //Only the normal speed sounds right, nobody listening is not an error
+            if !rewinding && speed == Speed::Factor(1.0) {
+                let _ = channels.audio.send(samples);
+            }
+            for gameboy in gameboys.iter_mut() {
+                gameboy.audio_samples();
+                for diagnostic in gameboy.take_diagnostics() {
+                    let _ = channels.diagnostic.send(diagnostic);
+                }
+            }
+
+            stats_frames += 1;
+            stats_cycles += u64::from(cycle);
+            let elapsed = stats_start.elapsed();
+            if elapsed >= STATS_PERIOD {
+                let seconds = elapsed.as_secs_f64();
+                let _ = channels.stats.send(Stats {
+                    fps: f64::from(stats_frames) / seconds,
+                    speed: stats_cycles as f64 / seconds / CYCLES_PER_SECOND * 100.0,
+                });
+                stats_start = Instant::now();
+                stats_frames = 0;
+                stats_cycles = 0;
+            }
+
+            match (speed, presented) {
+                (Speed::Uncapped, _) => deadline = Instant::now(),
+                //The display sets the pace, slightly faster than the Game Boy at 60 Hz
+                (_, Some(presented)) => {
+                    if presented.recv_timeout(MAX_LATENESS).is_err() {
+                        //Minimized windows may not present anything
+                        thread::sleep(Duration::from_secs_f64(f64::from(cycle) / CYCLES_PER_SECOND));
+                    }
+                    deadline = Instant::now();
+                }
+                (Speed::Factor(factor), None) => {
+                    //Against the previous deadline and not the current time, no drift
+                    deadline += Duration::from_secs_f64(f64::from(cycle) / CYCLES_PER_SECOND / factor);
+                    let now = Instant::now();
+                    if deadline > now {
+                        thread::sleep(deadline - now);
+                    } else if now - deadline > MAX_LATENESS {
+                        deadline = now;
+                    }
+                }
+            }
+        }
+
+        #[cfg(feature = "image")]
+        if let Some(recorder) = video {
+            finish_video(recorder);
+        }
+        if let Some(MovieMode::Record { movie, path }) = movie {
+            match fs::write(&path, movie.to_bytes()) {
+                Ok(()) => println!("Movie of {} frames written in {}", movie.frame_count(), path.display()),
+                Err(err) => println!("Failed to write the movie in {}: {}", path.display(), err),
+            }
+        }
+    })
+}
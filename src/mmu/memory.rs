@@ -1,30 +1,37 @@
 use core::panic;
-use std::sync::mpsc::{Receiver, Sender};
-use crate::cartridge;
 use crate::processor::clock::Clock;
+use crate::apu::sound::{Apu, SAMPLE_RATE};
 
 use crate::ppu::gpu::Gpu;
-use crate::input::{Input, Key};
+use crate::input::Input;
+use crate::serial::Serial;
 use crate::cartridge::MemoryBankController;
+use crate::state::{StateError, StateReader, StateWriter};
 
 
+//One second of interleaved stereo samples
+const MAX_SAMPLES: usize = SAMPLE_RATE as usize * 2;
+
 pub struct MemoryBus {
     pub(crate) rom: Box<dyn MemoryBankController>,
     pub(crate) wram: [u8; 0x2000], 
     pub(crate) hram: [u8; 0x80],
     pub(crate) gpu: Gpu,
-    pub(crate) screen_sender:Sender<[u32;23040]>,
     pub(crate) interrupt_flags: u8,
     pub(crate) interrupt_enabled: u8,
     pub(crate) input: Input,
     pub(crate) clock: Clock,
+    pub(crate) apu: Apu,
+    pub(crate) dma: u8,
+    //Next byte copied by the OAM DMA, None when no transfer is running
+    dma_index: Option<u16>,
+    dma_starting: bool,
+    pub(crate) serial: Serial,
 }
 
 impl MemoryBus {
 
-    pub fn new(rom_path: &str, screen_sender: Sender<[u32; 23040]>, key_receiver: Receiver<Key>) -> Self {
-        let cartridge = cartridge::new(rom_path);
-
+    pub fn new(cartridge: Box<dyn MemoryBankController>) -> Self {
         MemoryBus {
             rom: cartridge,
             interrupt_flags: 0,
@@ -32,9 +39,13 @@ impl MemoryBus {
             wram: [0_u8; 0x2000],
             hram: [0_u8; 0x80],
             gpu: Gpu::new(),
-            screen_sender,
-            input: Input::new(key_receiver),
+            input: Input::new(),
             clock: Clock::default(),
+            apu: Apu::new(),
+            dma: 0xFF,
+            dma_index: None,
+            dma_starting: false,
+            serial: Serial::new(),
         }
     }
 
@@ -45,13 +56,15 @@ impl MemoryBus {
             0x0000..=0x7FFF | 0xA000..=0xBFFF => self.rom.read_byte(address),                                   // ROM and RAM from cart
             0x8000..=0x9FFF => self.gpu.read_vram(address),                                                // GPU Vram
             0xC000..=0xFDFF => self.wram[(address & 0x1FFF) as usize],                                          // Working RAM with the echo one
+            0xFE00..=0xFE9F if self.dma_index.is_some() => 0xFF,                                                // OAM is busy during DMA
             0xFE00..=0xFE9F =>  self.gpu.read_oam(address),                                                // GPU Sprite
             0xFF00 => self.input.read(),                                                                        // Input read
-            //0xFF01..=0xFF02 => panic!("RSerial"),                                                             // Serial read needed to play multi not require here
+            0xFF01..=0xFF02 => self.serial.read(address),                                                       // Serial data and control
             0xFF04..=0xFF07 => self.clock.read(address),                                                        // Read Clock values
             0xFF0F => self.interrupt_flags,                                                                     // Interrupt flags
-            //0xFF10..=0xFF26 => panic!("RSound"),                                                              // Sound control not require here
-            //0xFF30..=0xFF3F => panic!("RSound"),                                                              // Sound wave pattern RAM not require here
+            0xFF10..=0xFF26 => self.apu.read(address),                                                          // Sound control
+            0xFF30..=0xFF3F => self.apu.read(address),                                                          // Sound wave pattern RAM
+            0xFF46 => self.dma,
             0xFF40..=0xFF4B => self.gpu.read_lcd_reg(address),
             0xFF4C..=0xFF7F => panic!("MMU ERROR: memory mapped I/O (read) (CGB only) not implemented"),
             0xFF80..=0xFFFE =>self.hram[(address & 0x7F) as usize],                                             // High RAM
@@ -66,14 +79,18 @@ impl MemoryBus {
             0x0000..=0x7FFF | 0xA000..=0xBFFF => self.rom.write_byte(address,byte),                             // ROM and RAM from cart
             0x8000..=0x9FFF => self.gpu.write_vram(address,byte),                                         // GPU Vram
             0xC000..=0xFDFF => self.wram[(address & 0x1FFF) as usize] = byte,                                   // Working RAM
+            0xFE00..=0xFE9F if self.dma_index.is_some() => (),                                                  // OAM is busy during DMA
             0xFE00..=0xFE9F => self.gpu.write_oam(address,byte),                                          // GPU Sprite
             0xFF00 => self.input.write(byte),                                                             // Input write
-            //0xFF01..=0xFF02 => panic!("WSerial"),                                                             // Serial write needed to play multi not require here
+            0xFF01..=0xFF02 => self.serial.write(address,byte),                                           // Serial data and control
             0xFF04..=0xFF07 => self.clock.write(address,byte),                                            // write Clock values
             0xFF0F => self.interrupt_flags = byte,                                                              // Interrupt flags
-            //0xFF10..=0xFF26 => panic!("WSound"),                                                              // Sound control
-            //0xFF30..=0xFF3F => panic!("WSound"),                                                              // Sound wave pattern RAM
-            0xFF46 => self.dma_into_oam(byte),
+            0xFF10..=0xFF26 => self.apu.write(address,byte),                                              // Sound control
+            0xFF30..=0xFF3F => self.apu.write(address,byte),                                              // Sound wave pattern RAM
+            0xFF46 => {
+                self.dma = byte;
+                self.dma_starting = true;
+            }
             0xFF40..=0xFF45 | 0xFF47..=0xFF4B => self.gpu.write_lcd_reg(address,byte),
             //0xFF4C..=0xFF7F => panic!((CGB only)),
             0xFF80..=0xFFFE =>self.hram[(address & 0x7F) as usize] = byte,                                      // High RAM
@@ -82,35 +99,80 @@ impl MemoryBus {
         }
     }
 
-    pub fn read_word(&self, address: u16) -> u16 {
-        u16::from(self.read_byte(address)) | (u16::from(self.read_byte(address + 1)) << 8)
-    }
-
-    pub fn write_word(&mut self, addr: u16, word: u16) {
-        self.write_byte(addr, (word & 0xFF) as u8);
-        self.write_byte(addr + 1 ,((word >> 8) & 0xFF) as u8);
-    }
-
     pub fn run(&mut self,cycle:u8){
-        self.gpu.run(self.screen_sender.clone(),cycle);
+        self.gpu.run(cycle);
         self.interrupt_flags |= self.gpu.interrupt;
         self.gpu.interrupt = 0;
 
-        self.input.run();
         self.interrupt_flags |= self.input.interrupt;
         self.input.interrupt = 0;
 
         self.clock.run((cycle * 4) as u32);
         self.interrupt_flags |= self.clock.interrupt;
         self.clock.interrupt=0;
+
+        self.serial.run(cycle);
+        self.interrupt_flags |= self.serial.interrupt;
+        self.serial.interrupt = 0;
+
+        self.apu.run((cycle * 4) as u32);
+        //Nobody took the samples, only keep the last second
+        if self.apu.samples.len() >= MAX_SAMPLES * 2 {
+            self.apu.samples.drain(..MAX_SAMPLES);
+        }
+
+        for _ in 0..cycle {
+            self.step_dma();
+        }
+    }
+
+    //The serial output and the samples are not part of the machine state
+    pub fn save_state(&self, state: &mut StateWriter) {
+        self.rom.save_state(state);
+        state.bytes(&self.wram);
+        state.bytes(&self.hram);
+        state.u8(self.interrupt_flags);
+        state.u8(self.interrupt_enabled);
+        state.u8(self.dma);
+        state.bool(self.dma_index.is_some());
+        state.u16(self.dma_index.unwrap_or(0));
+        state.bool(self.dma_starting);
+        self.serial.save_state(state);
+        self.gpu.save_state(state);
+        self.input.save_state(state);
+        self.clock.save_state(state);
+        self.apu.save_state(state);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.rom.load_state(state)?;
+        state.bytes(&mut self.wram)?;
+        state.bytes(&mut self.hram)?;
+        self.interrupt_flags = state.u8()?;
+        self.interrupt_enabled = state.u8()?;
+        self.dma = state.u8()?;
+        let dma_running = state.bool()?;
+        let dma_index = state.u16()?;
+        self.dma_index = if dma_running { Some(dma_index % 0xA0) } else { None };
+        self.dma_starting = state.bool()?;
+        self.serial.load_state(state)?;
+        self.gpu.load_state(state)?;
+        self.input.load_state(state)?;
+        self.clock.load_state(state)?;
+        self.apu.load_state(state)
     }
 
-    fn dma_into_oam(&mut self, dma_start: u8) {
-        // DMA start 0x0000, 0x0100, 0x0200, etc
-        let actual_dma_start = u16::from(dma_start) * 0x100;
-        for i in 0..0xA0_u16 {
-            let value = self.read_byte(actual_dma_start + i);
-            self.gpu.write_oam(i, value);
+    //Copy one byte each M-cycle, the transfer starts one cycle after the write and takes 160 cycles
+    fn step_dma(&mut self) {
+        if let Some(index) = self.dma_index {
+            // DMA start 0x0000, 0x0100, 0x0200, etc
+            let value = self.read_byte(u16::from(self.dma) * 0x100 + index);
+            self.gpu.write_oam(index, value);
+            self.dma_index = if index + 1 < 0xA0 { Some(index + 1) } else { None };
+        }
+        if self.dma_starting {
+            self.dma_starting = false;
+            self.dma_index = Some(0);
         }
     }
 }
@@ -0,0 +1,123 @@
+use std::fmt;
+
+use crate::state::{StateError, StateReader, StateWriter};
+use crate::{Buttons, GameBoy};
+
+//Movie layout: magic, version, CRC32 of the rom, the input settings, the save state the
+//recording starts from, then the held and turbo buttons of every frame, one byte each
+const MAGIC: &[u8; 4] = b"GBMV";
+const MOVIE_VERSION: u16 = 3;
+
+#[derive(Debug)]
+pub enum MovieError {
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    //The movie was recorded with another rom
+    RomMismatch,
+    Corrupt,
+    //The state it starts from can't be loaded
+    State(StateError),
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MovieError::InvalidMagic => write!(f, "not a movie"),
+            MovieError::UnsupportedVersion(version) => write!(f, "unsupported movie version {}", version),
+            MovieError::RomMismatch => write!(f, "the movie was recorded with another rom"),
+            MovieError::Corrupt => write!(f, "the movie is corrupt"),
+            MovieError::State(err) => write!(f, "the movie can't start: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for MovieError {}
+
+impl From<StateError> for MovieError {
+    fn from(err: StateError) -> Self {
+        match err {
+            StateError::InvalidMagic => MovieError::InvalidMagic,
+            StateError::UnsupportedVersion(version) => MovieError::UnsupportedVersion(version),
+            StateError::RomMismatch => MovieError::RomMismatch,
+            StateError::Corrupt => MovieError::Corrupt,
+        }
+    }
+}
+
+//Joypad state of every frame since a save state. Starting from a state, battery ram included,
+//makes the playback exact as the buttons are only read between frames.
+pub struct Movie {
+    rom_crc: u32,
+    //The buttons are recorded before the input settings change them
+    allow_opposite: bool,
+    turbo_rate: u8,
+    start: Vec<u8>,
+    //Held and turbo buttons
+    frames: Vec<(Buttons, Buttons)>,
+}
+
+impl Movie {
+    //Start recording from a power cycle, or from where the console is
+    pub fn record(gameboy: &mut GameBoy, from_reset: bool) -> Self {
+        if from_reset {
+            gameboy.reset();
+        }
+        Self {
+            rom_crc: gameboy.rom_crc,
+            allow_opposite: gameboy.cpu.bus.input.allow_opposite,
+            turbo_rate: gameboy.cpu.bus.input.turbo_rate,
+            start: gameboy.save_state(),
+            frames: Vec::new(),
+        }
+    }
+
+    //The buttons given to the frame about to run
+    pub fn push(&mut self, buttons: Buttons, turbo: Buttons) {
+        self.frames.push((buttons, turbo));
+    }
+
+    //Put the console back where the recording started
+    pub fn start(&self, gameboy: &mut GameBoy) -> Result<(), MovieError> {
+        if self.rom_crc != gameboy.rom_crc {
+            return Err(MovieError::RomMismatch);
+        }
+        gameboy.load_state(&self.start).map_err(MovieError::State)?;
+        gameboy.allow_opposite_directions(self.allow_opposite);
+        gameboy.set_turbo_rate(self.turbo_rate);
+        Ok(())
+    }
+
+    //Held and turbo buttons of a frame
+    pub fn frame(&self, index: usize) -> Option<(Buttons, Buttons)> {
+        self.frames.get(index).copied()
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = StateWriter::with_header(MAGIC, MOVIE_VERSION);
+        writer.u32(self.rom_crc);
+        writer.bool(self.allow_opposite);
+        writer.u8(self.turbo_rate);
+        writer.vec(&self.start);
+        let frames: Vec<u8> = self.frames.iter().flat_map(|(buttons, turbo)| [buttons.to_byte(), turbo.to_byte()]).collect();
+        writer.vec(&frames);
+        writer.finish()
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, MovieError> {
+        let mut reader = StateReader::with_header(data, MAGIC, MOVIE_VERSION)?;
+        Ok(Self {
+            rom_crc: reader.u32()?,
+            allow_opposite: reader.bool()?,
+            turbo_rate: reader.u8()?,
+            start: reader.owned_vec()?,
+            frames: reader.owned_vec()?
+                .chunks_exact(2)
+                .map(|frame| (Buttons::from_byte(frame[0]), Buttons::from_byte(frame[1])))
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{GameBoy, CYCLES_PER_FRAME};
+
+//Two Game Boys linked by a cable in memory. They run instruction by instruction so neither
+//gets more than one instruction ahead, and a transfer reaches the other one once it has
+//caught up with the time the clock started. The whole session is reproducible
+pub struct LinkedPair {
+    gameboys: [GameBoy; 2],
+    //M-cycles the first one is ahead of the second
+    lead: i32,
+}
+
+impl LinkedPair {
+    //What was plugged in their link ports is removed
+    pub fn new(mut first: GameBoy, mut second: GameBoy) -> Self {
+        first.connect_serial(None);
+        second.connect_serial(None);
+        Self { gameboys: [first, second], lead: 0 }
+    }
+
+    pub fn gameboys(&self) -> &[GameBoy; 2] {
+        &self.gameboys
+    }
+
+    pub fn gameboys_mut(&mut self) -> &mut [GameBoy; 2] {
+        &mut self.gameboys
+    }
+
+    //Run until the next VBlank of the first one, the second one follows. Both LCDs have the
+    //same period so the second one finishes its frames at a constant offset
+    pub fn run_frame(&mut self) -> u32 {
+        let [first, second] = &mut self.gameboys;
+        let mut cycles = 0;
+        first.cpu.bus.gpu.frame_ready = false;
+        while !first.cpu.bus.gpu.frame_ready && cycles < CYCLES_PER_FRAME {
+            let spent = first.cpu.run();
+            cycles += u32::from(spent);
+            self.lead += i32::from(spent);
+            while self.lead > 0 {
+                self.lead -= i32::from(second.cpu.run());
+                connect(second, first);
+            }
+            connect(first, second);
+        }
+        for gameboy in &mut self.gameboys {
+            gameboy.cpu.bus.gpu.frame_ready = false;
+            gameboy.cpu.bus.input.end_frame();
+        }
+        cycles
+    }
+}
+
+//A transfer started by the sender's clock shifts the receiver's byte too if it waits for
+//the external clock, otherwise nothing answers and 0xFF comes in
+fn connect(sender: &mut GameBoy, receiver: &mut GameBoy) {
+    let Some(sent) = sender.cpu.bus.serial.take_started() else {
+        return;
+    };
+    if receiver.cpu.bus.serial.waits_external_clock() {
+        let received = receiver.cpu.bus.serial.clock_external(sent);
+        sender.cpu.bus.serial.set_received(received);
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::mpsc::Sender;
+use crate::state::{StateError, StateReader, StateWriter};
 
 pub struct Gpu {
     pub(crate) vram: [u8; 0x2000],
@@ -21,6 +21,8 @@ pub struct Gpu {
     pub(crate) screen_buffer: [u32; 160*144],
     pub(crate) interrupt: u8,
     pub(crate) render_counter: u8,
+    //A whole frame is in screen_buffer, set at VBlank
+    pub(crate) frame_ready: bool,
 }
 
 
@@ -47,6 +49,7 @@ impl Gpu {
             screen_buffer: [0_u32; 160*144],
             interrupt: 0,
             render_counter: 0,
+            frame_ready: false,
         }
     }
     pub fn read_lcd_reg(&self, address:u16) -> u8{
@@ -118,7 +121,7 @@ impl Gpu {
         0x9800
     }
 
-    pub fn run(&mut self, x: Sender<[u32;23040]>,cycle: u8){
+    pub fn run(&mut self, cycle: u8){
         //ecran on ?
         if self.lcdc & 0x80 == 0 {
             return;
@@ -133,7 +136,7 @@ impl Gpu {
 
             if self.ly == 144 {
                 self.interrupt |= 0x01;
-                x.send(self.screen_buffer).unwrap();
+                self.frame_ready = true;
             }
 
             self.step_bgwin();
@@ -144,6 +147,39 @@ impl Gpu {
     }
 
 
+    //The screen is not saved, it is drawn again during the next frame
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.bytes(&self.vram);
+        state.bytes(&self.oam);
+        for register in [self.lcdc, self.stat, self.scy, self.scx, self.ly, self.lyc, self.wy, self.wx, self.bgp_value, self.obp0_value, self.obp1_value] {
+            state.u8(register);
+        }
+        state.u8(self.interrupt);
+        state.u8(self.render_counter);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        state.bytes(&mut self.vram)?;
+        state.bytes(&mut self.oam)?;
+        for register in [&mut self.lcdc, &mut self.stat, &mut self.scy, &mut self.scx, &mut self.ly, &mut self.lyc, &mut self.wy, &mut self.wx, &mut self.bgp_value, &mut self.obp0_value, &mut self.obp1_value] {
+            *register = state.u8()?;
+        }
+        self.bgp = value_to_palette(self.bgp_value);
+        self.obp0 = value_to_palette(self.obp0_value);
+        self.obp1 = value_to_palette(self.obp1_value);
+        self.interrupt = state.u8()?;
+        //Lines are 114 cycles, ly stays below 154
+        self.render_counter = state.u8()? % 114;
+        self.ly %= 154;
+        Ok(())
+    }
+
+    //LCD in low power mode (STOP), only a blank screen is shown
+    pub fn blank(&mut self){
+        self.screen_buffer = [0xffffff; 160*144];
+        self.frame_ready = true;
+    }
+
     pub fn step_bgwin(&mut self){
         //bg on ? or Vblank
         if self.lcdc & 0x01 ==0 || self.ly >= 144 {
@@ -0,0 +1,261 @@
+use std::path::PathBuf;
+
+use crate::serial::SerialDevice;
+
+//Game Boy Printer, writing each sheet it prints in a PNG file
+//https://gbdev.io/pandocs/Gameboy_Printer.html
+//
+//Packets: 0x88 0x33, command, compression, data length (little endian), data, checksum of
+//everything after the magic bytes (little endian), then two bytes during which the printer
+//answers 0x81 and its status. It answers 0x00 to every other byte.
+const MAGIC: [u8; 2] = [0x88, 0x33];
+const ALIVE: u8 = 0x81;
+
+const INIT: u8 = 0x01;
+const PRINT: u8 = 0x02;
+const DATA: u8 = 0x04;
+//0x0F asks for the status, which every packet answers anyway
+
+//Status bits
+const CHECKSUM_ERROR: u8 = 0x01;
+const BUSY: u8 = 0x02;
+const IMAGE_FULL: u8 = 0x04;
+const UNPROCESSED: u8 = 0x08;
+
+//8 KiB of RAM, a data packet is 2 rows of 20 tiles (16 lines)
+const MEMORY_SIZE: usize = 0x2000;
+const BAND_SIZE: usize = 640;
+const BAND_HEIGHT: usize = 16;
+const WIDTH: usize = 160;
+//A margin of 1 feeds as much paper as a band
+const FEED_HEIGHT: usize = BAND_HEIGHT;
+//Status packets answered busy after a print, games wait for it to end
+const BUSY_STATUSES: u8 = 4;
+
+//Shades 0 (white) to 3 (black) on paper
+const SHADES: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Phase {
+    Magic(usize),
+    Command,
+    Compression,
+    Length(usize),
+    Data,
+    Checksum(usize),
+    Alive,
+    Status,
+}
+
+pub struct Printer {
+    directory: PathBuf,
+    phase: Phase,
+    command: u8,
+    compressed: bool,
+    length: u16,
+    packet: Vec<u8>,
+    checksum: u16,
+    received_checksum: u16,
+    status: u8,
+    busy: u8,
+    //Tile data of the next print, decompressed
+    memory: Vec<u8>,
+    //Shades of the lines printed since the paper was last cut, 160 per line
+    paper: Vec<u8>,
+}
+
+impl Printer {
+    //The sheets go in the directory as print_001.png, print_002.png... after the existing ones
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            directory: directory.into(),
+            phase: Phase::Magic(0),
+            command: 0,
+            compressed: false,
+            length: 0,
+            packet: Vec::new(),
+            checksum: 0,
+            received_checksum: 0,
+            status: 0,
+            busy: 0,
+            memory: Vec::new(),
+            paper: Vec::new(),
+        }
+    }
+
+    //Returns what the printer sends at the same time
+    fn receive(&mut self, byte: u8) -> u8 {
+        if matches!(self.phase, Phase::Command | Phase::Compression | Phase::Length(_) | Phase::Data) {
+            self.checksum = self.checksum.wrapping_add(u16::from(byte));
+        }
+        match self.phase {
+            //Anything else starts over, the second magic byte may be the first one of a retry
+            Phase::Magic(index) => {
+                self.phase = if byte == MAGIC[index] {
+                    if index == 0 { Phase::Magic(1) } else { Phase::Command }
+                } else if byte == MAGIC[0] {
+                    Phase::Magic(1)
+                } else {
+                    Phase::Magic(0)
+                };
+                self.checksum = 0;
+            }
+            Phase::Command => {
+                self.command = byte;
+                self.phase = Phase::Compression;
+            }
+            Phase::Compression => {
+                self.compressed = byte & 0x01 != 0;
+                self.phase = Phase::Length(0);
+            }
+            Phase::Length(0) => {
+                self.length = u16::from(byte);
+                self.phase = Phase::Length(1);
+            }
+            Phase::Length(_) => {
+                self.length |= u16::from(byte) << 8;
+                self.packet.clear();
+                self.phase = if self.length == 0 { Phase::Checksum(0) } else { Phase::Data };
+            }
+            Phase::Data => {
+                self.packet.push(byte);
+                if self.packet.len() == usize::from(self.length) {
+                    self.phase = Phase::Checksum(0);
+                }
+            }
+            Phase::Checksum(0) => {
+                self.received_checksum = u16::from(byte);
+                self.phase = Phase::Checksum(1);
+            }
+            Phase::Checksum(_) => {
+                self.received_checksum |= u16::from(byte) << 8;
+                self.phase = Phase::Alive;
+                if self.received_checksum == self.checksum {
+                    self.status &= !CHECKSUM_ERROR;
+                    self.run_command();
+                } else {
+                    self.status |= CHECKSUM_ERROR;
+                }
+            }
+            Phase::Alive => {
+                self.phase = Phase::Status;
+                return ALIVE;
+            }
+            Phase::Status => {
+                self.phase = Phase::Magic(0);
+                return self.status();
+            }
+        }
+        0x00
+    }
+
+    fn status(&mut self) -> u8 {
+        if self.busy > 0 {
+            self.busy -= 1;
+            return self.status | BUSY;
+        }
+        self.status
+    }
+
+    fn run_command(&mut self) {
+        match self.command {
+            INIT => {
+                self.memory.clear();
+                self.status = 0;
+                self.busy = 0;
+            }
+            DATA if self.packet.is_empty() => self.status |= IMAGE_FULL,
+            DATA => {
+                let packet = std::mem::take(&mut self.packet);
+                if self.compressed {
+                    decompress(&packet, &mut self.memory);
+                } else {
+                    self.memory.extend_from_slice(&packet);
+                }
+                self.memory.truncate(MEMORY_SIZE);
+                self.status |= UNPROCESSED;
+            }
+            PRINT if self.packet.len() == 4 => {
+                //Sheets, margins, palette, exposure. Only one copy of each sheet is written
+                let margins = self.packet[1];
+                let palette = self.packet[2];
+                self.print(margins >> 4, margins & 0x0F, palette);
+                self.memory.clear();
+                self.status &= !(IMAGE_FULL | UNPROCESSED);
+                self.busy = BUSY_STATUSES;
+            }
+            _ => (),
+        }
+    }
+
+    //A margin after the image ends the sheet, without one the next print continues it
+    fn print(&mut self, before: u8, after: u8, palette: u8) {
+        //0 is used by some games for the usual palette
+        let palette = if palette == 0 { 0xE4 } else { palette };
+        self.feed(before);
+        for band in self.memory.chunks_exact(BAND_SIZE) {
+            for line in 0..BAND_HEIGHT {
+                for tile in 0..WIDTH / 8 {
+                    let offset = ((line / 8) * (WIDTH / 8) + tile) * 16 + (line % 8) * 2;
+                    let (low, high) = (band[offset], band[offset + 1]);
+                    for bit in (0..8).rev() {
+                        let color = (((high >> bit) & 1) << 1) | ((low >> bit) & 1);
+                        self.paper.push((palette >> (color * 2)) & 0x03);
+                    }
+                }
+            }
+        }
+        if after > 0 {
+            self.feed(after);
+            self.cut();
+        }
+    }
+
+    fn feed(&mut self, margin: u8) {
+        self.paper.resize(self.paper.len() + usize::from(margin) * FEED_HEIGHT * WIDTH, 0);
+    }
+
+    //Write the sheet and start a new one
+    fn cut(&mut self) {
+        if self.paper.is_empty() {
+            return;
+        }
+        let pixels: Vec<u8> = self.paper.drain(..).map(|shade| SHADES[usize::from(shade)]).collect();
+        let height = (pixels.len() / WIDTH) as u32;
+        let path = (1..).map(|number| self.directory.join(format!("print_{:03}.png", number)))
+            .find(|path| !path.exists())
+            .expect("a free file name");
+        match image::save_buffer(&path, &pixels, WIDTH as u32, height, image::ColorType::L8) {
+            Ok(()) => println!("Printed {}", path.display()),
+            Err(err) => println!("Failed to write the print in {}: {}", path.display(), err),
+        }
+    }
+}
+
+//Runs of a byte (bit 7 set, length + 2) or raw bytes (length + 1)
+fn decompress(data: &[u8], memory: &mut Vec<u8>) {
+    let mut bytes = data.iter();
+    while let Some(&control) = bytes.next() {
+        if control & 0x80 != 0 {
+            let Some(&byte) = bytes.next() else {
+                break;
+            };
+            memory.resize(memory.len() + usize::from(control & 0x7F) + 2, byte);
+        } else {
+            memory.extend(bytes.by_ref().take(usize::from(control) + 1));
+        }
+    }
+}
+
+impl SerialDevice for Printer {
+    fn exchange(&mut self, sent: u8) -> u8 {
+        self.receive(sent)
+    }
+}
+
+//A sheet printed without a margin after it is still written when the printer is unplugged
+impl Drop for Printer {
+    fn drop(&mut self) {
+        self.cut();
+    }
+}
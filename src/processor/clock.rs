@@ -1,63 +1,187 @@
+use crate::state::{StateError, StateReader, StateWriter};
+
 #[derive(Default)]
 pub struct Clock {
-    div:u8,
-    counter_div:u32,
+    //16 bits system counter incremented every T-cycle, DIV is its upper byte
+    counter: u16,
     tima:u8,
     tma:u8,
     tac:u8,
+    //TIMA overflowed during the last M-cycle, it reads 0 until the reload on the next one
+    overflow: bool,
+    //M-cycle during which TIMA is reloaded with TMA
+    reloading: bool,
     pub interrupt:u8,
-    counter:u32
 }
 
 impl Clock {
     pub fn read(&self, address: u16)->u8{
         match address {
-            0xFF04 => self.div,
+            0xFF04 => (self.counter >> 8) as u8,
             0xFF05 => self.tima,
             0xFF06 => self.tma,
-            0xFF07 => self.tac,
+            0xFF07 => self.tac | 0xF8,
             _ => panic!("Error reading clock")
         }
     }
 
     pub fn write(&mut self, address: u16, value: u8){
         match address {
-            0xFF04 => self.div=0,
-            0xFF05 => self.tima=value,
-            0xFF06 => self.tma=value,
+            0xFF04 => {
+                //Reset the whole counter, the selected bit can fall and increment TIMA
+                let old_signal = self.timer_signal();
+                self.counter = 0;
+                self.check_falling_edge(old_signal);
+            }
+            0xFF05 => {
+                //Ignored during the reload, cancel the reload and the interrupt if written just before
+                if !self.reloading {
+                    self.tima = value;
+                    self.overflow = false;
+                }
+            }
+            0xFF06 => {
+                self.tma = value;
+                //TMA written during the reload goes to TIMA too
+                if self.reloading {
+                    self.tima = value;
+                }
+            }
             0xFF07 => {
-                self.tac=value;
-                self.tima += 1;
+                //Disabling the timer or changing the selected bit can also make a falling edge
+                let old_signal = self.timer_signal();
+                self.tac = value & 0x07;
+                self.check_falling_edge(old_signal);
             },
             _ => panic!("Error writing clock")
         }
     }
 
-    //Follow https://gbdev.io/pandocs/Timer_and_Divider_Registers.html
+    //Follow https://gbdev.io/pandocs/Timer_Obscure_Behaviour.html
     pub fn run(&mut self, ticks:u32){
-        self.counter_div += ticks;
-        if self.counter_div >= 0xFF {
-            self.div = self.div.wrapping_add(1);
-            self.counter_div -= 256;
+        for _ in 0..ticks / 4 {
+            self.step();
         }
+    }
 
-        if self.tac  & 0x4 != 0 {
-            self.counter += ticks;
-            let limit = match self.tac & 0x3 {
-                0 => 1024,
-                1 => 16,
-                2 => 64,
-                3 => 256,
-                _ => panic!("Error of limit timer")
-            };
-            while self.counter >= limit {
-                self.tima = self.tima.wrapping_add(1);
-                if self.tima == 0 {
-                    self.tima = self.tma;
-                    self.interrupt |= 0x04;
-                }
-                self.counter -= limit;
-            }
+    //One M-cycle
+    fn step(&mut self) {
+        self.reloading = false;
+        if self.overflow {
+            self.overflow = false;
+            self.tima = self.tma;
+            self.interrupt |= 0x04;
+            self.reloading = true;
+        }
+
+        let old_signal = self.timer_signal();
+        self.counter = self.counter.wrapping_add(4);
+        self.check_falling_edge(old_signal);
+    }
+
+    //Bit of the system counter selected by TAC and with the timer enable
+    fn timer_signal(&self) -> bool {
+        let bit = match self.tac & 0x3 {
+            0 => 9,
+            1 => 3,
+            2 => 5,
+            3 => 7,
+            _ => panic!("Error of limit timer")
+        };
+        self.tac & 0x4 != 0 && (self.counter >> bit) & 1 != 0
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.u16(self.counter);
+        state.u8(self.tima);
+        state.u8(self.tma);
+        state.u8(self.tac);
+        state.bool(self.overflow);
+        state.bool(self.reloading);
+        state.u8(self.interrupt);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.counter = state.u16()?;
+        self.tima = state.u8()?;
+        self.tma = state.u8()?;
+        self.tac = state.u8()? & 0x07;
+        self.overflow = state.bool()?;
+        self.reloading = state.bool()?;
+        self.interrupt = state.u8()?;
+        Ok(())
+    }
+
+    fn check_falling_edge(&mut self, old_signal: bool) {
+        if old_signal && !self.timer_signal() {
+            let (tima, overflow) = self.tima.overflowing_add(1);
+            self.tima = tima;
+            self.overflow |= overflow;
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Enabled, bit 3 of the counter: TIMA goes up every 16 T-cycles
+    fn timer(tima: u8) -> Clock {
+        let mut clock = Clock::default();
+        clock.write(0xFF07, 0x05);
+        clock.write(0xFF05, tima);
+        clock
+    }
+
+    #[test]
+    fn div_write_makes_a_falling_edge() {
+        let mut clock = timer(0);
+        clock.run(8);
+        assert_eq!(clock.read(0xFF05), 0);
+        clock.write(0xFF04, 0x12);
+        assert_eq!(clock.read(0xFF04), 0);
+        assert_eq!(clock.read(0xFF05), 1);
+    }
+
+    #[test]
+    fn tac_change_makes_a_falling_edge() {
+        let mut clock = timer(0);
+        clock.run(8);
+        //Bit 9 is low
+        clock.write(0xFF07, 0x04);
+        assert_eq!(clock.read(0xFF05), 1);
+        //Disabled while the selected bit is high
+        clock.write(0xFF07, 0x05);
+        clock.run(8);
+        clock.write(0xFF07, 0x01);
+        assert_eq!(clock.read(0xFF05), 2);
+    }
+
+    #[test]
+    fn tima_reads_zero_for_a_cycle_after_overflow() {
+        let mut clock = timer(0xFF);
+        clock.write(0xFF06, 0x80);
+        clock.run(16);
+        assert_eq!(clock.read(0xFF05), 0);
+        assert_eq!(clock.interrupt, 0);
+        clock.run(4);
+        assert_eq!(clock.read(0xFF05), 0x80);
+        assert_eq!(clock.interrupt, 0x04);
+    }
+
+    #[test]
+    fn tma_write_during_reload_goes_to_tima() {
+        let mut clock = timer(0xFF);
+        clock.write(0xFF06, 0x80);
+        clock.run(20);
+        clock.write(0xFF06, 0x33);
+        assert_eq!(clock.read(0xFF05), 0x33);
+        //TIMA itself can't be written then
+        clock.write(0xFF05, 0x44);
+        assert_eq!(clock.read(0xFF05), 0x33);
+        //Nor is TMA copied once the reload is over
+        clock.run(4);
+        clock.write(0xFF06, 0x55);
+        assert_eq!(clock.read(0xFF05), 0x33);
+    }
+}
@@ -1,9 +1,16 @@
-use std::sync::mpsc::{Receiver, Sender};
-use crate::{processor::registres::Registers, mmu::memory::MemoryBus};
-use crate::input::Key;
+use crate::{processor::registres::{FlagsRegister, Registers}, mmu::memory::MemoryBus};
+use crate::cartridge::MemoryBankController;
+use crate::state::{StateError, StateReader, StateWriter};
 
 use super::instructions::{ArithmeticTarget, RstTarget, Instruction, JumpTest, StackTarget, LoadByteSource, LoadType, LoadByteTarget, LoadWordSource, LoadWordTarget, JumpCondition};
 
+//Events the emulator can't handle by itself, reported to the front end
+#[derive(Debug)]
+pub enum Diagnostic {
+    //Illegal opcode executed, the CPU hard-locks until reset
+    Locked { opcode: u8, pc: u16 },
+}
+
 pub struct Cpu {
     pub(crate) registers: Registers,
     pub(crate) pc: u16,
@@ -11,36 +18,67 @@ pub struct Cpu {
     pub(crate) sp: u16,
     pub(crate) interrupt_master_enable: bool,
     pub(crate) halt: bool,
-    pub(crate) ei: u8,
-    pub(crate) di: u8,
+    //HALT executed with an interrupt pending and IME=0 or just set by EI. PC is not
+    //incremented by the next fetch, or the interrupt returns to the HALT
+    pub(crate) halt_bug: bool,
+    //STOP mode, CPU and LCD are off until a joypad line goes low
+    pub(crate) stopped: bool,
+    //Illegal opcode executed, only the rest of the system keeps running
+    pub(crate) locked: bool,
+    //EI was executed, IME is set once the next instruction starts
+    pub(crate) ei: bool,
+    //IME was set by EI for the instruction running, only lasts during it
+    ei_enabled: bool,
     pub(crate) cycle:u8,
-    pub(crate) stop:Receiver<bool>,
+    pub(crate) diagnostics: Vec<Diagnostic>,
 }
 
 impl Cpu {
     // pub const CPU_FREQ: u32 =4_194_304;
 
-    pub fn new(rom_path: &str, screen_sender: Sender<[u32; 23040]>, key_receiver: Receiver<Key>, stop_receiver: Receiver<bool>) -> Self {
+    pub fn with_cartridge(cartridge: Box<dyn MemoryBankController>) -> Self {
 
         Cpu {
             registers: Registers ::new(),
             pc: 0x0100,
-            bus: MemoryBus::new(rom_path, screen_sender, key_receiver),
+            bus: MemoryBus::new(cartridge),
             sp: 0xFFFE,
             halt: false,
+            halt_bug: false,
+            stopped: false,
+            locked: false,
             interrupt_master_enable: true,
-            ei: 0,
-            di: 0,
+            ei: false,
+            ei_enabled: false,
             cycle: 0,
-            stop: stop_receiver,
+            diagnostics: Vec::new(),
         }
     }
     pub fn read_next_byte(&mut self) -> u8 {
-        self.bus.read_byte(self.pc+1)
+        self.read(self.pc.wrapping_add(1))
     }
 
     pub fn read_next_word(&mut self) -> u16 {
-        self.bus.read_word(self.pc+1)
+        let least_significant_byte = self.read(self.pc.wrapping_add(1)) as u16;
+        let most_significant_byte = self.read(self.pc.wrapping_add(2)) as u16;
+        (most_significant_byte << 8) | least_significant_byte
+    }
+
+    //Let the rest of the system (timer, PPU, DMA...) run for one M-cycle
+    pub fn tick(&mut self) {
+        self.cycle += 1;
+        self.bus.run(1);
+    }
+
+    //Every memory access takes one M-cycle, done at the end of it
+    pub fn read(&mut self, address: u16) -> u8 {
+        self.tick();
+        self.bus.read_byte(address)
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        self.tick();
+        self.bus.write_byte(address, value);
     }
 
     pub fn execute(&mut self, instruction: Instruction) -> u16{
@@ -83,7 +121,7 @@ impl Cpu {
                         self.registers.a = new_value;
                     },
                     ArithmeticTarget::AddressHL => {
-                        let value = self.bus.read_byte(self.registers.get_hl());
+                        let value = self.read(self.registers.get_hl());
                         let new_value = self.add(value);
                         self.registers.a = new_value;
                     },
@@ -138,21 +176,25 @@ impl Cpu {
                     }
                     //pas de modifications de flag
                     ArithmeticTarget::BC => {
+                        self.tick();
                         let value = self.registers.get_bc();
                         let new_value = value.wrapping_add(1);
                         self.registers.set_bc(new_value);
                     }
                     ArithmeticTarget::DE => {
+                        self.tick();
                         let value = self.registers.get_de();
                         let new_value = value.wrapping_add(1);
                         self.registers.set_de(new_value);
                     }
                     ArithmeticTarget::HL => {
+                        self.tick();
                         let value = self.registers.get_hl();
                         let new_value = value.wrapping_add(1);
                         self.registers.set_hl(new_value);
                     }
                     ArithmeticTarget::SP => {
+                        self.tick();
                         let value = self.sp;
                         let new_value = value.wrapping_add(1);
                         self.sp = new_value;
@@ -170,21 +212,25 @@ impl Cpu {
                     }
                     //pas de modifications de flag
                     ArithmeticTarget::BC => {
+                        self.tick();
                         let value = self.registers.get_bc();
                         let new_value = value.wrapping_sub(1);
                         self.registers.set_bc(new_value);
                     }
                     ArithmeticTarget::DE => {
+                        self.tick();
                         let value = self.registers.get_de();
                         let new_value = value.wrapping_sub(1);
                         self.registers.set_de(new_value);
                     }
                     ArithmeticTarget::HL => {
+                        self.tick();
                         let value = self.registers.get_hl();
                         let new_value = value.wrapping_sub(1);
                         self.registers.set_hl(new_value);
                     }
                     ArithmeticTarget::SP => {
+                        self.tick();
                         let value = self.sp;
                         let new_value = value.wrapping_sub(1);
                         self.sp = new_value;
@@ -296,16 +342,22 @@ impl Cpu {
                             LoadByteSource::E => self.registers.e,
                             LoadByteSource::H => self.registers.h,
                             LoadByteSource::L => self.registers.l,
-                            LoadByteSource::AddressBC => self.bus.read_byte(self.registers.get_bc()),
-                            LoadByteSource::AddressDE => self.bus.read_byte(self.registers.get_de()),
-                            LoadByteSource::AddressHLP => self.bus.read_byte(self.registers.get_hlp()),
-                            LoadByteSource::AddressHLM => self.bus.read_byte(self.registers.get_hlm()),
-                            LoadByteSource::AddressC => self.bus.read_byte(0xFF00 | (self.registers.c as u16)),
+                            LoadByteSource::AddressBC => self.read(self.registers.get_bc()),
+                            LoadByteSource::AddressDE => self.read(self.registers.get_de()),
+                            LoadByteSource::AddressHLP => {
+                                let address = self.registers.get_hlp();
+                                self.read(address)
+                            }
+                            LoadByteSource::AddressHLM => {
+                                let address = self.registers.get_hlm();
+                                self.read(address)
+                            }
+                            LoadByteSource::AddressC => self.read(0xFF00 | (self.registers.c as u16)),
                             LoadByteSource::D8 => self.read_next_byte(),
-                            LoadByteSource::AddressHL => self.bus.read_byte(self.registers.get_hl()),
+                            LoadByteSource::AddressHL => self.read(self.registers.get_hl()),
                             LoadByteSource::Address16 => {
                                 let address = self.read_next_word();
-                                self.bus.read_byte(address)
+                                self.read(address)
                             }
                             _ => { panic!("TODO: implement other sources") }
                         };
@@ -318,15 +370,21 @@ impl Cpu {
                             LoadByteTarget::E => self.registers.e = source_value,
                             LoadByteTarget::H => self.registers.h = source_value,
                             LoadByteTarget::L => self.registers.l = source_value,
-                            LoadByteTarget::AddressBC => self.bus.write_byte(self.registers.get_bc(), source_value),
-                            LoadByteTarget::AddressHL => self.bus.write_byte(self.registers.get_hl(), source_value),
-                            LoadByteTarget::AddressDE => self.bus.write_byte(self.registers.get_de(), source_value),
-                            LoadByteTarget::AddressHLP =>self.bus.write_byte(self.registers.get_hlp(), source_value),
-                            LoadByteTarget::AddressHLM => self.bus.write_byte(self.registers.get_hlm(), source_value),
-                            LoadByteTarget::AddressC => self.bus.write_byte(0xFF00 | (self.registers.c as u16) , source_value),
+                            LoadByteTarget::AddressBC => self.write(self.registers.get_bc(), source_value),
+                            LoadByteTarget::AddressHL => self.write(self.registers.get_hl(), source_value),
+                            LoadByteTarget::AddressDE => self.write(self.registers.get_de(), source_value),
+                            LoadByteTarget::AddressHLP => {
+                                let address = self.registers.get_hlp();
+                                self.write(address, source_value)
+                            }
+                            LoadByteTarget::AddressHLM => {
+                                let address = self.registers.get_hlm();
+                                self.write(address, source_value)
+                            }
+                            LoadByteTarget::AddressC => self.write(0xFF00 | (self.registers.c as u16) , source_value),
                             LoadByteTarget::Address16 => {
                                 let address = self.read_next_word();
-                                self.bus.write_byte(address,source_value);
+                                self.write(address,source_value);
                             } 
                             _ => { panic!("TODO: implement other targets") }
                         };
@@ -351,12 +409,16 @@ impl Cpu {
                                 self.pc+=2;
                                 val
                             },
-                            LoadWordSource::HL => self.registers.get_hl(),
+                            LoadWordSource::HL => {
+                                self.tick();
+                                self.registers.get_hl()
+                            }
                             LoadWordSource::SP => self.sp,
                             LoadWordSource::SPR8 => {
                                 let r8 = self.read_next_byte() as i8 as i32;
                                 let sp = self.sp as i32;
                                 let res = sp.wrapping_add(r8);
+                                self.tick();
 
                                 self.registers.f.zero=false;
                                 self.registers.f.subtract=false;
@@ -372,12 +434,17 @@ impl Cpu {
                             LoadWordTarget::SP => self.sp = source_value,
                             LoadWordTarget::Address16 => {
                                 let address = self.read_next_word();
-                                self.bus.write_word(address, source_value) },
+                                self.write(address, (source_value & 0xFF) as u8);
+                                self.write(address.wrapping_add(1), (source_value >> 8) as u8);
+                            },
                         };
                         match (source, target) {
                             (LoadWordSource::SPR8,LoadWordTarget::HL) => {
                                 self.pc+2
                             }
+                            (_, LoadWordTarget::Address16) => {
+                                self.pc+3
+                            }
                             _ => {
                                 self.pc+1
                             }
@@ -396,7 +463,7 @@ impl Cpu {
                             LoadByteSource::Address8 => {
                                 let address = 0xFF00 | (self.read_next_byte() as u16);
                                 
-                                self.bus.read_byte(address)
+                                self.read(address)
                             },
                             _ => { panic!("TODO: implement other sources") }
                         };
@@ -405,7 +472,7 @@ impl Cpu {
                             LoadByteTarget::A => self.registers.a = source_value,
                             LoadByteTarget::Address8 => {
                                 let address =  0xFF00 | self.read_next_byte() as u16;
-                                self.bus.write_byte( address, source_value)
+                                self.write( address, source_value)
                             },
                             _ => { panic!("TODO: implement other targets") }
                         };
@@ -446,6 +513,10 @@ impl Cpu {
             }
 
             Instruction::Ret(test) => {
+                //Checking the condition takes a cycle
+                if !matches!(test, JumpTest::Always) {
+                    self.tick();
+                }
                 let jump_condition = self.getjump_condition(test);
                 self.return_(jump_condition)
             }
@@ -466,31 +537,63 @@ impl Cpu {
 
             Instruction::Reti => {
                 self.pc = self.pop();
-                self.ei = 1;
+                self.tick();
+                //Unlike EI there is no delay
+                self.interrupt_master_enable = true;
                 self.pc
             },
 
-            Instruction::Stop => self.pc + 2,
+            //https://gbdev.io/pandocs/Reducing_Power_Consumption.html#using-the-stop-instruction
+            Instruction::Stop => {
+                let button_held = self.bus.input.any_line_low();
+                let interrupt_pending = self.pending_interrupts() != 0;
+                match (button_held, interrupt_pending) {
+                    //1 byte opcode, mode doesn't change and DIV isn't reset
+                    (true, true) => self.pc + 1,
+                    //2 bytes opcode, HALT mode is entered and DIV isn't reset
+                    (true, false) => {
+                        self.halt = true;
+                        self.pc + 2
+                    }
+                    (false, true) => {
+                        self.enter_stop();
+                        self.pc + 1
+                    }
+                    (false, false) => {
+                        self.enter_stop();
+                        self.pc + 2
+                    }
+                }
+            },
 
-            Instruction::Halt =>{self.halt = true;
-                self.pc + 1},
+            Instruction::Halt => {
+                if (!self.interrupt_master_enable || self.ei_enabled) && self.pending_interrupts() != 0 {
+                    //HALT bug: the CPU doesn't halt and the next byte is read twice. After EI
+                    //the interrupt comes first and returns to the HALT, which runs again
+                    self.halt_bug = true;
+                } else {
+                    self.halt = true;
+                }
+                self.pc + 1
+            },
 
             Instruction::Ei => {
-                //Like DI enabling take 1 more instructions to update IME flag
-                self.ei=2;
+                //IME is only set after the next instruction, see run
+                self.ei = true;
                 self.pc + 1
             },
 
             Instruction::Di => {
-                //Like EI disabling take 1 more instructions to update IME flag
-                self.di=2;
+                //No delay on hardware, it also cancels a pending EI
+                self.interrupt_master_enable = false;
+                self.ei = false;
                 self.pc + 1
             },
 
             Instruction::Nop => self.pc + 1,
 
             Instruction::Rst(npc) => {
-                let old_pc = self.pc+1;
+                let old_pc = self.pc.wrapping_add(1);
                 self.push(old_pc);
                 match npc {
                     RstTarget::Rst00H => self.pc = 0x00,
@@ -506,7 +609,8 @@ impl Cpu {
             },
 
             Instruction::Addsp() => self.execute_addsp(),
-            Instruction::PrefixCB => panic!("value 6connu"),
+            //0xCB is always decoded with the next byte, never executed alone
+            Instruction::PrefixCB => unreachable!("0xCB decoded without its opcode"),
         }
 
     }
@@ -557,6 +661,7 @@ impl Cpu {
 
         };
         let (new_value, did_overflow) = self.registers.get_hl().overflowing_add(value);
+        self.tick();
         self.registers.f.subtract = false;
         self.registers.f.carry = did_overflow;
         self.registers.f.half_carry = (self.registers.get_hl() & 0x07FF) + (value & 0x07FF) > 0x07FF;
@@ -568,6 +673,8 @@ impl Cpu {
         let signed_byte = self.read_next_byte() as u16;
         let value = self.sp;
         let (new_value, did_overflow) = self.sp.overflowing_add(signed_byte);
+        self.tick();
+        self.tick();
 
         self.registers.f.subtract = false;
         self.registers.f.carry = did_overflow;
@@ -586,7 +693,7 @@ impl Cpu {
             ArithmeticTarget::E => self.registers.e,
             ArithmeticTarget::H => self.registers.h,
             ArithmeticTarget::L => self.registers.l,
-            ArithmeticTarget::AddressHL => self.bus.read_byte(self.registers.get_hl()),
+            ArithmeticTarget::AddressHL => self.read(self.registers.get_hl()),
             ArithmeticTarget::D8 => self.read_next_byte(),
             _=> panic!("value 8connu"),
         };
@@ -620,7 +727,7 @@ impl Cpu {
             ArithmeticTarget::E => self.registers.e,
             ArithmeticTarget::H => self.registers.h,
             ArithmeticTarget::L => self.registers.l,
-            ArithmeticTarget::AddressHL => self.bus.read_byte(self.registers.get_hl()),
+            ArithmeticTarget::AddressHL => self.read(self.registers.get_hl()),
             ArithmeticTarget::D8 => self.read_next_byte(),
             _ => panic!("value 9connu"),
 
@@ -649,7 +756,7 @@ impl Cpu {
             ArithmeticTarget::E => self.registers.e,
             ArithmeticTarget::H => self.registers.h,
             ArithmeticTarget::L => self.registers.l,
-            ArithmeticTarget::AddressHL => self.bus.read_byte(self.registers.get_hl()),
+            ArithmeticTarget::AddressHL => self.read(self.registers.get_hl()),
             ArithmeticTarget::D8 => self.read_next_byte(),
             _ => panic!("value 10connu"),
 
@@ -681,7 +788,7 @@ impl Cpu {
             ArithmeticTarget::E => self.registers.e,
             ArithmeticTarget::H => self.registers.h,
             ArithmeticTarget::L => self.registers.l,
-            ArithmeticTarget::AddressHL => self.bus.read_byte(self.registers.get_hl()),
+            ArithmeticTarget::AddressHL => self.read(self.registers.get_hl()),
             ArithmeticTarget::D8 => self.read_next_byte(),
             _ => panic!("value 11connu"),
 
@@ -710,7 +817,7 @@ impl Cpu {
             ArithmeticTarget::E => self.registers.e,
             ArithmeticTarget::H => self.registers.h,
             ArithmeticTarget::L => self.registers.l,
-            ArithmeticTarget::AddressHL => self.bus.read_byte(self.registers.get_hl()),
+            ArithmeticTarget::AddressHL => self.read(self.registers.get_hl()),
             ArithmeticTarget::D8 => self.read_next_byte(),
             _ => panic!("value 12connu"),
 
@@ -740,7 +847,7 @@ impl Cpu {
             ArithmeticTarget::E => self.registers.e,
             ArithmeticTarget::H => self.registers.h,
             ArithmeticTarget::L => self.registers.l,
-            ArithmeticTarget::AddressHL => self.bus.read_byte(self.registers.get_hl()),
+            ArithmeticTarget::AddressHL => self.read(self.registers.get_hl()),
             ArithmeticTarget::D8 => self.read_next_byte(),
             _ => panic!("value 13connu"),
 
@@ -769,7 +876,7 @@ impl Cpu {
             ArithmeticTarget::E => self.registers.e,
             ArithmeticTarget::H => self.registers.h,
             ArithmeticTarget::L => self.registers.l,
-            ArithmeticTarget::AddressHL => self.bus.read_byte(self.registers.get_hl()),
+            ArithmeticTarget::AddressHL => self.read(self.registers.get_hl()),
             ArithmeticTarget::D8 => self.read_next_byte(),
             _ => panic!("value 14connu"),
         };
@@ -826,9 +933,9 @@ impl Cpu {
             }
             ArithmeticTarget::AddressHL => {
                 let address=self.registers.get_hl();
-                let value = self.bus.read_byte(address);
+                let value = self.read(address);
                 let new_value = value.wrapping_add(1);
-                self.bus.write_byte(address, new_value);
+                self.write(address, new_value);
                 new_value
             }
             _ => panic!("value 15connu")
@@ -879,9 +986,9 @@ impl Cpu {
             }
             ArithmeticTarget::AddressHL => {
                 let address=self.registers.get_hl();
-                let value = self.bus.read_byte(address);
+                let value = self.read(address);
                 let new_value = value.wrapping_sub(1);
-                self.bus.write_byte(address, new_value);
+                self.write(address, new_value);
                 new_value
             }
             _ => panic!("value 16connu")
@@ -990,7 +1097,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.read_byte(address) // You may need to implement memory read here.
+                self.read(address) // You may need to implement memory read here.
             },
             _ => panic!("value 17connu"),
         };
@@ -1014,7 +1121,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.read_byte(address) // Read the value from memory.
+                self.read(address) // Read the value from memory.
             },
             _ => panic!("value 18connu,{:?}",target),
         };
@@ -1034,7 +1141,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l = value,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.write_byte(address, value); // Write the new value back to memory.
+                self.write(address, value); // Write the new value back to memory.
             },
             _ => panic!("value 19connu"),
         }
@@ -1051,7 +1158,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.read_byte(address) // Read the value from memory.
+                self.read(address) // Read the value from memory.
             },
             _ => panic!("value 20connu"),
         };
@@ -1071,7 +1178,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l = value,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.write_byte(address, value); // Write the new value back to memory.
+                self.write(address, value); // Write the new value back to memory.
             },
             _ => panic!("value 21connu"),
         }
@@ -1088,7 +1195,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.read_byte(address) // Read the value from memory
+                self.read(address) // Read the value from memory
             },
             _ => panic!("value 22connu"),
         };
@@ -1109,7 +1216,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l = value,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.write_byte(address, value); // Write the new value back to memory.
+                self.write(address, value); // Write the new value back to memory.
             },
             _ => panic!("value 23connu")
         }
@@ -1132,7 +1239,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.read_byte(address) // Read the value from memory.
+                self.read(address) // Read the value from memory.
             },
             _ => panic!("value 24connu"),
         };
@@ -1167,7 +1274,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.read_byte(address) // Read the value from memory.
+                self.read(address) // Read the value from memory.
             },
             _ => panic!("value 27connu"),
         };
@@ -1191,7 +1298,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l = value,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.write_byte(address, value); // Write the new value back to memory.
+                self.write(address, value); // Write the new value back to memory.
             },
             _ => panic!("value 28connu"),
         }
@@ -1214,7 +1321,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.read_byte(address) // Read the value from memory.
+                self.read(address) // Read the value from memory.
             },
             _ => panic!("value 29connu"),
         };
@@ -1239,7 +1346,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l = value,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.write_byte(address, value); // Write the new value back to memory.
+                self.write(address, value); // Write the new value back to memory.
             },
             _ => panic!("value 30connu"),
         }
@@ -1262,7 +1369,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.read_byte(address) // Read the value from memory.
+                self.read(address) // Read the value from memory.
             },
             _ => panic!("value 31connu"),
         };
@@ -1287,7 +1394,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l = value,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.write_byte(address, value); // Write the new value back to memory.
+                self.write(address, value); // Write the new value back to memory.
             },
             _ => panic!("value 32connu"),
         }
@@ -1310,7 +1417,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.read_byte(address) // Read the value from memory.
+                self.read(address) // Read the value from memory.
             },
             _ => panic!("value 33connu"),
         };
@@ -1333,7 +1440,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l = value,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.write_byte(address, value); // Write the new value back to memory.
+                self.write(address, value); // Write the new value back to memory.
             },
             _ => panic!("value 3connu"),
         }
@@ -1356,7 +1463,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.read_byte(address) // Read the value from memory.
+                self.read(address) // Read the value from memory.
             },
             _ => panic!("value 37connu"),
         };
@@ -1378,7 +1485,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l = value,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.write_byte(address, value); // Write the new value back to memory.
+                self.write(address, value); // Write the new value back to memory.
             },
             _ => panic!("value 38connu"),
         }
@@ -1401,7 +1508,7 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l,
             ArithmeticTarget::AddressHL => {
                 let address = self.registers.get_hl();
-                self.bus.read_byte(address) // Read the value from memory.
+                self.read(address) // Read the value from memory.
             },
             _ => panic!("value 1connu"),
         };
@@ -1422,53 +1529,115 @@ impl Cpu {
     }
 
     pub fn push(&mut self, value: u16) {
+        //SP is decremented before the writes
+        self.tick();
         self.sp = self.sp.wrapping_sub(1);
-        self.bus.write_byte(self.sp, ((value & 0xFF00) >> 8) as u8);
+        self.write(self.sp, ((value & 0xFF00) >> 8) as u8);
 
         self.sp = self.sp.wrapping_sub(1);
-        self.bus.write_byte(self.sp, (value & 0xFF) as u8);
+        self.write(self.sp, (value & 0xFF) as u8);
 
     }
 
     pub fn pop(&mut self) -> u16 {
-        let lsb = self.bus.read_byte(self.sp) as u16;
+        let lsb = self.read(self.sp) as u16;
         self.sp = self.sp.wrapping_add(1);
 
-        let msb = self.bus.read_byte(self.sp) as u16;
+        let msb = self.read(self.sp) as u16;
         self.sp = self.sp.wrapping_add(1);
 
         (msb << 8) | lsb
     }
 
+    pub fn save_state(&self, state: &mut StateWriter) {
+        let registers = &self.registers;
+        for register in [registers.a, u8::from(registers.f.clone()), registers.b, registers.c, registers.d, registers.e, registers.h, registers.l] {
+            state.u8(register);
+        }
+        state.u16(self.pc);
+        state.u16(self.sp);
+        for flag in [self.interrupt_master_enable, self.ei, self.halt, self.halt_bug, self.stopped, self.locked] {
+            state.bool(flag);
+        }
+        self.bus.save_state(state);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.registers.a = state.u8()?;
+        self.registers.f = FlagsRegister::from(state.u8()?);
+        let registers = &mut self.registers;
+        for register in [&mut registers.b, &mut registers.c, &mut registers.d, &mut registers.e, &mut registers.h, &mut registers.l] {
+            *register = state.u8()?;
+        }
+        self.pc = state.u16()?;
+        self.sp = state.u16()?;
+        for flag in [&mut self.interrupt_master_enable, &mut self.ei, &mut self.halt, &mut self.halt_bug, &mut self.stopped, &mut self.locked] {
+            *flag = state.bool()?;
+        }
+        self.bus.load_state(state)
+    }
+
+    //Return the number of M-cycles spent, the rest of the system already ran during them
     pub fn run(&mut self) -> u8{
-        self.update_ime();
+        self.cycle = 0;
 
-        let interrupt = self.stat_interruption();
-        if interrupt > 0 {
-            self.cycle=4;
-        } else if self.halt {
-            self.cycle=1; // noop
-        } else {
+        if self.stopped {
+            //Everything is frozen but the joypad, time still goes by for the pacing
+            if self.bus.input.any_line_low() {
+                self.stopped = false;
+            }
+            self.cycle = 1;
+            return self.cycle;
+        }
+
+        //Nothing wakes a locked CPU, not even interrupts
+        if self.locked {
+            self.tick();
+            return self.cycle;
+        }
+
+        if self.halt {
+            if self.pending_interrupts() == 0 {
+                self.tick(); // noop
+                return self.cycle;
+            }
+            //Any pending interrupt ends HALT even with IME=0, leaving it takes one cycle
+            self.halt = false;
+            self.tick();
+        }
+
+        if !self.dispatch_interrupt() {
+            //The instruction after EI runs before any interrupt, and can still be a DI
+            if self.ei {
+                self.ei = false;
+                self.interrupt_master_enable = true;
+                self.ei_enabled = true;
+            }
             self.step();
+            self.ei_enabled = false;
         }
-        self.bus.run(self.cycle);
         self.cycle
     }
 
 
     pub fn step(&mut self) {
-        let mut instruction_byte = self.bus.read_byte(self.pc);
+        let mut instruction_byte = self.read(self.pc);
+        if self.halt_bug {
+            self.halt_bug = false;
+            self.pc = self.pc.wrapping_sub(1);
+        }
         let prefixed = instruction_byte == 0xCB;
         if prefixed {
-            instruction_byte = self.bus.read_byte(self.pc + 1);
+            instruction_byte = self.read(self.pc.wrapping_add(1));
         }
-        let (instruction,cycle) = Instruction::from_byte(instruction_byte, prefixed);
-        self.cycle=cycle;
+        let instruction = Instruction::from_byte(instruction_byte, prefixed);
         let next_pc = if let Some(instruction) = instruction {
             self.execute(instruction)
         } else {
-            let description = format!("0x{}{:x}", if prefixed { "cb" } else { "" }, instruction_byte);
-            panic!("Unkown instruction found for: {}", description)
+            //https://gbdev.io/pandocs/CPU_Instruction_Set.html the CPU hangs on illegal opcodes
+            self.locked = true;
+            self.diagnostics.push(Diagnostic::Locked { opcode: instruction_byte, pc: self.pc });
+            self.pc
         };
         self.pc = next_pc;
 
@@ -1479,11 +1648,11 @@ impl Cpu {
         match ju {
 
             JumpCondition::Address16 => {
+                //The address is read even if the jump is not taken
+                let address = self.read_next_word();
                 if should_jump {
-                    let least_significant_byte = self.bus.read_byte(self.pc + 1) as u16;
-                    let most_significant_byte = self.bus.read_byte(self.pc + 2) as u16;
-                    self.cycle+=1;
-                    (most_significant_byte << 8) | least_significant_byte
+                    self.tick();
+                    address
                 } else {
                     self.pc.wrapping_add(3)
                 }
@@ -1496,19 +1665,19 @@ impl Cpu {
 
     pub fn call(&mut self, should_jump: bool) -> u16 {
         let next_pc = self.pc.wrapping_add(3);
+        let address = self.read_next_word();
         if should_jump {
             self.push(next_pc);
-            self.cycle+=3;
-            self.read_next_word()
+            address
         } else {
             next_pc
         }
     }
 
     pub fn jump_relative(&mut self, condition: bool) {
+        let r8=self.read_next_byte() as i8;
         if condition {
-            self.cycle+=1;
-            let r8=self.read_next_byte() as i8;
+            self.tick();
             let new_pc = ((self.pc as i32) + 2 + r8 as i32) as u16;
             self.pc = new_pc;
         } else {
@@ -1519,69 +1688,65 @@ impl Cpu {
 
     pub fn return_(&mut self, should_jump: bool) -> u16 {
         if should_jump {
-            self.cycle+=3;
-            self.pop()
+            let address = self.pop();
+            self.tick();
+            address
         } else {
             self.pc.wrapping_add(1)
         }
     }
 
-    fn update_ime(&mut self) {
-        if self.di > 0 {
-            self.di -= 1;
-            if self.di == 0 {
-                self.interrupt_master_enable = false;
-            }
-        }
-
-        if self.ei > 0 {
-            self.ei -= 1;
-            if self.ei == 0 {
-                self.interrupt_master_enable = true;
-            }
-        }
+    fn enter_stop(&mut self) {
+        self.stopped = true;
+        self.bus.write_byte(0xFF04, 0);
+        self.bus.gpu.blank();
     }
 
-    fn stat_interruption(&mut self) -> u16 {
-
-        //No interruption enable
-        if !self.interrupt_master_enable && !self.halt {
-            return 0;
+    //https://gbdev.io/pandocs/Interrupts.html#interrupt-handling
+    //Return true if an interrupt was dispatched, it takes 5 M-cycles
+    fn dispatch_interrupt(&mut self) -> bool {
+        if !self.interrupt_master_enable || self.pending_interrupts() == 0 {
+            return false;
         }
 
-        //Flag on bus which is called https://gbdev.io/pandocs/Interrupts.html#ffff--ie-interrupt-enable
-        let interruption = self.bus.interrupt_flags & self.bus.interrupt_enabled; //Operation on binary to get the right flag
-
-        if interruption == 0 {
-            return 0;
-        }
-
-        //Halt case return
-        self.halt = false;
-        if !self.interrupt_master_enable {
-            return 0;
-        }
-
-        //Set to false cause will take one now
         self.interrupt_master_enable = false;
-        let interrupt_jump_addresses: [u16; 5] = [0x40, 0x48, 0x50, 0x58, 0x60];
-
-        for (flag_number, interrupt_jump_address) in interrupt_jump_addresses.iter().enumerate() {
+        self.ei = false;
+        //After the HALT bug the return address is the HALT itself
+        let old_pc = if self.halt_bug {
+            self.halt_bug = false;
+            self.pc.wrapping_sub(1)
+        } else {
+            self.pc
+        };
 
-            let flag = 1 << (flag_number as u8);
+        //2 wait cycles, the 2 writes then the jump
+        self.tick();
+        self.tick();
+        self.sp = self.sp.wrapping_sub(1);
+        self.write(self.sp, (old_pc >> 8) as u8);
+        //The high byte can overwrite IE, the interrupt is only chosen now
+        let interruption = self.pending_interrupts();
+        self.sp = self.sp.wrapping_sub(1);
+        self.write(self.sp, (old_pc & 0xFF) as u8);
 
-            if interruption & flag > 0 {
-                self.bus.interrupt_flags &= !flag;
-                let old_pc = self.pc;
-                self.push(old_pc);
-                self.pc = *interrupt_jump_address;
-                return 4;
-            }
-        }
-        panic!("Unknown interrupt was not handled! 0b{:08b}", interruption);
+        self.pc = if interruption == 0 {
+            //Cancelled, no IF bit is acknowledged
+            0x0000
+        } else {
+            let flag_number = interruption.trailing_zeros() as u16;
+            self.bus.interrupt_flags &= !(1 << flag_number);
+            0x40 + flag_number * 8
+        };
+        self.tick();
+        true
     }
 
 
+    //Flag on bus which is called https://gbdev.io/pandocs/Interrupts.html#ffff--ie-interrupt-enable
+    fn pending_interrupts(&self) -> u8 {
+        self.bus.interrupt_flags & self.bus.interrupt_enabled & 0x1F //Operation on binary to get the right flag
+    }
+
     fn getjump_condition(&self, test:JumpTest) -> bool{
         match test {
             JumpTest::NotZero => !self.registers.f.zero,
@@ -1603,10 +1768,12 @@ impl Cpu {
             ArithmeticTarget::L => self.registers.l = value,
             ArithmeticTarget::AddressHL => {
             let address = self.registers.get_hl();
-            self.bus.write_byte(address, value); // Write the new value back to memory.
+            self.write(address, value); // Write the new value back to memory.
             },
             _ => panic!("value 2connu"),
         }
     }
 }
 
+
+
@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+
+use crate::GameBoy;
+
+//Ring buffer of save states taken every few frames. Only the newest one is kept whole,
+//each older one is the xor with the state after it, where the runs of zeros are skipped
+pub struct Rewind {
+    interval: u32,
+    budget: usize,
+    frame: u32,
+    newest: Option<Vec<u8>>,
+    //Oldest first
+    deltas: VecDeque<Vec<u8>>,
+    deltas_size: usize,
+}
+
+impl Rewind {
+    //A state every interval frames, the oldest ones are dropped past budget bytes
+    pub fn new(interval: u32, budget: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            budget,
+            frame: 0,
+            newest: None,
+            deltas: VecDeque::new(),
+            deltas_size: 0,
+        }
+    }
+
+    //Called once per emulated frame
+    pub fn record(&mut self, gameboy: &GameBoy) {
+        self.frame += 1;
+        if self.frame < self.interval {
+            return;
+        }
+        self.frame = 0;
+
+        let state = gameboy.save_state();
+        if let Some(newest) = self.newest.take() {
+            if newest.len() == state.len() {
+                let delta = encode(&state, &newest);
+                self.deltas_size += delta.len();
+                self.deltas.push_back(delta);
+            } else {
+                //Another rom was loaded, the older states are useless
+                self.clear();
+            }
+        }
+        self.newest = Some(state);
+
+        while self.memory_used() > self.budget {
+            match self.deltas.pop_front() {
+                Some(delta) => self.deltas_size -= delta.len(),
+                None => break,
+            }
+        }
+    }
+
+    //Go back to the newest state and forget it, false when there is nothing left
+    pub fn rewind(&mut self, gameboy: &mut GameBoy) -> bool {
+        let Some(newest) = self.newest.take() else {
+            return false;
+        };
+        if gameboy.load_state(&newest).is_err() {
+            self.clear();
+            return false;
+        }
+
+        //The oldest state is kept, holding the key stays on it
+        self.newest = Some(match self.deltas.pop_back() {
+            Some(delta) => {
+                self.deltas_size -= delta.len();
+                decode(&newest, &delta)
+            }
+            None => newest,
+        });
+        self.frame = 0;
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.deltas.clear();
+        self.deltas_size = 0;
+        self.frame = 0;
+    }
+
+    pub fn memory_used(&self) -> usize {
+        self.deltas_size + self.newest.as_ref().map_or(0, Vec::len)
+    }
+}
+
+//Pairs of (zeros to skip, length of the xor bytes that follow), as varints
+fn encode(base: &[u8], state: &[u8]) -> Vec<u8> {
+    let mut delta = Vec::new();
+    let mut position = 0;
+    while position < state.len() {
+        let start = position;
+        while position < state.len() && base[position] == state[position] {
+            position += 1;
+        }
+        let zeros = position - start;
+
+        let start = position;
+        while position < state.len() && base[position] != state[position] {
+            position += 1;
+        }
+        write_varint(&mut delta, zeros);
+        write_varint(&mut delta, position - start);
+        delta.extend(base[start..position].iter().zip(&state[start..position]).map(|(a, b)| a ^ b));
+    }
+    delta
+}
+
+fn decode(base: &[u8], delta: &[u8]) -> Vec<u8> {
+    let mut state = base.to_vec();
+    let mut position = 0;
+    let mut index = 0;
+    while index < delta.len() {
+        position += read_varint(delta, &mut index);
+        let length = read_varint(delta, &mut index);
+        for (byte, xor) in state[position..position + length].iter_mut().zip(&delta[index..index + length]) {
+            *byte ^= xor;
+        }
+        position += length;
+        index += length;
+    }
+    state
+}
+
+//7 bits per byte, the high bit set when more bytes follow
+fn write_varint(data: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        data.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+    data.push(value as u8);
+}
+
+fn read_varint(data: &[u8], index: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[*index];
+        *index += 1;
+        value |= usize::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
@@ -0,0 +1,203 @@
+use std::io::Write;
+
+use crate::state::{StateError, StateReader, StateWriter};
+
+//M-cycles per bit with the internal clock, 8192 Hz
+const CYCLES_PER_BIT: u16 = 128;
+
+//Something plugged in the link port
+pub trait SerialDevice: Send {
+    //A transfer with the internal clock starts with the byte sent, returns the byte received
+    fn exchange(&mut self, sent: u8) -> u8;
+
+    //Polled while a transfer waits for the external clock, with the byte that will be sent,
+    //at the pace of the bits and when SB or SC is written
+    //Returns the byte received once the other side has clocked it
+    fn external_clock(&mut self, _sent: u8) -> Option<u8> {
+        None
+    }
+
+    //SC stopped waiting for the external clock, the byte polled with is not offered any more
+    fn cancel(&mut self) {}
+}
+
+//Writes the bytes sent on stdout as they come, nothing answers. This is how Blargg's test
+//roms print their results
+pub struct StdoutSink;
+
+impl SerialDevice for StdoutSink {
+    fn exchange(&mut self, sent: u8) -> u8 {
+        let mut stdout = std::io::stdout();
+        let _ = stdout.write_all(&[sent]);
+        let _ = stdout.flush();
+        0xFF
+    }
+}
+
+//Link port, SB and SC
+//https://gbdev.io/pandocs/Serial_Data_Transfer_(Link_Cable).html
+pub struct Serial {
+    //SB, shifted out from bit 7 while the byte received comes in from bit 0
+    data: u8,
+    //SC bits 7 (transfer running) and 0 (internal clock)
+    control: u8,
+    //Byte coming in during the transfer
+    received: u8,
+    //Bits left to shift
+    bits: u8,
+    //M-cycles until the next bit, or the next poll of the device while waiting for the
+    //external clock
+    counter: u16,
+    pub interrupt: u8,
+    //Without a device the line stays high and 0xFF comes in
+    pub(crate) device: Option<Box<dyn SerialDevice>>,
+    //Every byte sent since power on when capturing, not part of the machine state
+    pub(crate) output: Vec<u8>,
+    pub(crate) capturing: bool,
+    //A transfer with the internal clock started, for the Game Boy linked in memory
+    started: bool,
+}
+
+impl Serial {
+    pub fn new() -> Self {
+        Self {
+            data: 0,
+            control: 0,
+            received: 0xFF,
+            bits: 0,
+            counter: 0,
+            interrupt: 0,
+            device: None,
+            output: Vec::new(),
+            capturing: false,
+            started: false,
+        }
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            0xFF01 => self.data,
+            //The unused bits read as 1
+            0xFF02 => self.control | 0x7E,
+            _ => panic!("Error reading serial"),
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF01 => {
+                self.data = value;
+                //The device is told the new byte right away
+                if self.waits_external_clock() {
+                    self.counter = 0;
+                }
+            }
+            0xFF02 => {
+                self.control = value & 0x81;
+                self.bits = 0;
+                self.counter = 0;
+                if self.control != 0x80 {
+                    if let Some(device) = self.device.as_mut() {
+                        device.cancel();
+                    }
+                }
+                //With the external clock the other side drives the transfer, with nobody
+                //plugged it never ends
+                if self.control == 0x81 {
+                    self.capture();
+                    self.received = match self.device.as_mut() {
+                        Some(device) => device.exchange(self.data),
+                        None => 0xFF,
+                    };
+                    self.bits = 8;
+                    self.counter = CYCLES_PER_BIT;
+                    self.started = true;
+                }
+            }
+            _ => panic!("Error writing serial"),
+        }
+    }
+
+    pub fn run(&mut self, cycles: u8) {
+        //Polled at the pace of the bits, not on every M-cycle
+        if self.waits_external_clock() {
+            if u16::from(cycles) < self.counter {
+                self.counter -= u16::from(cycles);
+                return;
+            }
+            self.counter = CYCLES_PER_BIT;
+            let received = self.device.as_mut().and_then(|device| device.external_clock(self.data));
+            if let Some(received) = received {
+                self.capture();
+                self.data = received;
+                self.control = 0;
+                self.interrupt |= 0x08;
+            }
+            return;
+        }
+        //Shifted by either clock, the other Game Boy's runs at the same pace
+        if self.control & 0x80 == 0 || self.bits == 0 {
+            return;
+        }
+        let mut cycles = u16::from(cycles);
+        while cycles >= self.counter && self.bits > 0 {
+            cycles -= self.counter;
+            self.counter = CYCLES_PER_BIT;
+            self.bits -= 1;
+            self.data = (self.data << 1) | ((self.received >> self.bits) & 1);
+            if self.bits == 0 {
+                self.control &= 0x01;
+                self.interrupt |= 0x08;
+            }
+        }
+        self.counter -= cycles.min(self.counter);
+    }
+
+    fn capture(&mut self) {
+        if self.capturing {
+            self.output.push(self.data);
+        }
+    }
+
+    //The byte sent by a transfer with the internal clock that just started
+    pub(crate) fn take_started(&mut self) -> Option<u8> {
+        std::mem::take(&mut self.started).then_some(self.data)
+    }
+
+    pub(crate) fn waits_external_clock(&self) -> bool {
+        self.control == 0x80 && self.bits == 0
+    }
+
+    //The other Game Boy clocks the transfer waiting for it, returns the byte it gets back
+    pub(crate) fn clock_external(&mut self, received: u8) -> u8 {
+        self.capture();
+        self.received = received;
+        self.bits = 8;
+        self.counter = CYCLES_PER_BIT;
+        self.data
+    }
+
+    //Replaces what the device answered when the transfer started
+    pub(crate) fn set_received(&mut self, received: u8) {
+        self.received = received;
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.u8(self.data);
+        state.u8(self.control);
+        state.u8(self.received);
+        state.u8(self.bits);
+        state.u16(self.counter);
+        state.u8(self.interrupt);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.data = state.u8()?;
+        self.control = state.u8()? & 0x81;
+        self.received = state.u8()?;
+        self.bits = state.u8()?.min(8);
+        self.counter = state.u16()?.min(CYCLES_PER_BIT);
+        self.interrupt = state.u8()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+
+//Save state layout: magic, version, CRC32 of the rom, then every component writes
+//its fields one after the other in little endian
+const MAGIC: &[u8; 4] = b"GBST";
+pub const STATE_VERSION: u16 = 3;
+
+#[derive(Debug)]
+pub enum StateError {
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    //The state was made with another rom
+    RomMismatch,
+    //Truncated data or a size that doesn't match the machine
+    Corrupt,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::InvalidMagic => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(version) => write!(f, "unsupported save state version {}", version),
+            StateError::RomMismatch => write!(f, "the save state belongs to another rom"),
+            StateError::Corrupt => write!(f, "the save state is corrupt"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+pub(crate) struct StateWriter {
+    data: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new(rom_crc: u32) -> Self {
+        let mut writer = Self::with_header(MAGIC, STATE_VERSION);
+        writer.u32(rom_crc);
+        writer
+    }
+
+    //Other files written the same way, like movies
+    pub fn with_header(magic: &[u8; 4], version: u16) -> Self {
+        let mut writer = Self { data: Vec::new() };
+        writer.bytes(magic);
+        writer.u16(version);
+        writer
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    //Fixed size data, the reader knows the length
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    //Variable size data, prefixed by its length
+    pub fn vec(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.bytes(bytes);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+pub(crate) struct StateReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8], rom_crc: u32) -> Result<Self, StateError> {
+        let mut reader = Self::with_header(data, MAGIC, STATE_VERSION)?;
+        if reader.u32()? != rom_crc {
+            return Err(StateError::RomMismatch);
+        }
+        Ok(reader)
+    }
+
+    pub fn with_header(data: &'a [u8], magic: &[u8; 4], version: u16) -> Result<Self, StateError> {
+        let mut reader = Self { data, position: 0 };
+        if reader.take(magic.len()).map_err(|_| StateError::InvalidMagic)? != magic {
+            return Err(StateError::InvalidMagic);
+        }
+        let found = reader.u16()?;
+        if found != version {
+            return Err(StateError::UnsupportedVersion(found));
+        }
+        Ok(reader)
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], StateError> {
+        let bytes = self.data.get(self.position..self.position + length).ok_or(StateError::Corrupt)?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, StateError> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u16(&mut self) -> Result<u16, StateError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, StateError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn bytes(&mut self, destination: &mut [u8]) -> Result<(), StateError> {
+        destination.copy_from_slice(self.take(destination.len())?);
+        Ok(())
+    }
+
+    //The length must match the destination, the machine doesn't change size
+    pub fn vec(&mut self, destination: &mut [u8]) -> Result<(), StateError> {
+        if self.u32()? as usize != destination.len() {
+            return Err(StateError::Corrupt);
+        }
+        self.bytes(destination)
+    }
+
+    //Data of any length written by vec
+    pub fn owned_vec(&mut self) -> Result<Vec<u8>, StateError> {
+        let length = self.u32()? as usize;
+        Ok(self.take(length)?.to_vec())
+    }
+}
+
+//CRC-32 (IEEE), identifies the rom a state was made with
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFF_u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}